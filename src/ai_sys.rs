@@ -1,9 +1,14 @@
 use crate::components::*;
-use crate::events::{SpawnGuidedMissileEvent, SpawnMineEvent};
-use crate::ships::load_rammer_ship;
-use crate::traits::*;
+use crate::events::{BossPhaseEvent, ImpulseEvent, SpawnGuidedMissileEvent, SpawnMineEvent};
+use crate::geometry::*;
+use crate::ship_parts::*;
+use crate::spawn_sys::spawn_ship;
+use crate::steering::*;
+use bevy::ecs::query::WorldQuery;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use libm::atan2f;
+use rand::prelude::*;
 use std::f32::consts::PI;
 
 /// Takes a transform A, a turn speed, and the angle between points A and B, and rotates the transform
@@ -16,11 +21,11 @@ fn turn_toward(transform: &mut Transform, turn_speed: f32, angle_between: f32) {
     }
 }
 
-fn angle_between(transform_a: &Transform, transform_b: &Transform) -> f32 {
+fn angle_between(transform_a: &Transform, transform_b: &Transform, arena: &Arena) -> f32 {
     // Calculate the angle between a and b from the perspective of a.
-    let y = transform_b.translation.y - transform_a.translation.y;
-    let x = transform_b.translation.x - transform_a.translation.x;
-    let target_angle = atan2f(y, x);
+    // Aim along the shortest path, which may cross the edge of the arena.
+    let delta = wrapped_delta(transform_a.translation, transform_b.translation, arena);
+    let target_angle = atan2f(delta.y, delta.x);
 
     let angle_between = transform_a
         .rotation
@@ -29,33 +34,398 @@ fn angle_between(transform_a: &Transform, transform_b: &Transform) -> f32 {
     angle_between
 }
 
+/// Returns the angle the shooter needs to turn through to lead its target with the given weapon, scaled by the
+/// shooter's accuracy. Ships move by their velocity every frame while projectiles move by theirs every second, so the
+/// target's velocity is converted to per-second using the frame time.
+fn lead_angle(
+    shooter: &Transform,
+    shooter_speed: f32,
+    weapon: &WeaponSystem,
+    target: &TargetInfo,
+    accuracy: Option<&Accuracy>,
+    delta_seconds: f32,
+    arena: &Arena,
+) -> f32 {
+    let accuracy = accuracy.map_or(0.0, |a| a.value);
+    if accuracy <= 0.0 || delta_seconds <= 0.0 {
+        return angle_between(shooter, &target.transform, arena);
+    }
+    let projectile_speed = (weapon.proj_speed + shooter_speed) * MS_TO_PS;
+    let target_velocity = target.velocity * MS_TO_PS / delta_seconds * accuracy;
+    // A target too fast to catch gets shot at directly.
+    let aim = intercept_point(
+        shooter.translation,
+        target.transform.translation,
+        target_velocity,
+        projectile_speed,
+        arena,
+    )
+    .unwrap_or(target.transform.translation);
+    angle_between(shooter, &Transform::from_translation(aim), arena)
+}
+
+// How wide a berth AI ships give obstacles, and how far ahead they look for them, in pixels.
+const STEERING_CLEARANCE: f32 = 30.0;
+const STEERING_LOOK_AHEAD: f32 = 250.0;
+
+/// What AI ships can see of the arena around them.
+#[derive(SystemParam)]
+pub struct Surroundings<'w> {
+    targets: Res<'w, TargetCache>,
+    obstacles: Res<'w, ObstacleGrid>,
+    arena: Res<'w, Arena>,
+}
+
+impl Surroundings<'_> {
+    /// Adds the steering every AI ship wants on top of `goal`: keeping clear of other ships and swerving around
+    /// anything in its path. Obstacles in `ignore`, usually the ship itself and whatever it is attacking, don't count.
+    fn cruise(
+        &self,
+        goal: Vec3,
+        transform: &Transform,
+        velocity: &Velocity,
+        ignore: &[Entity],
+    ) -> Vec3 {
+        let position = transform.translation;
+        // Look where the ship is going, or where it is pointing if it isn't going anywhere yet.
+        let heading = if velocity.velocity.length() > 1.0 {
+            velocity.velocity
+        } else {
+            transform.up()
+        };
+        goal + separation(
+            position,
+            STEERING_CLEARANCE * 2.0,
+            ignore,
+            &self.obstacles,
+            &self.arena,
+        ) * 1.5
+            + avoid_obstacles(
+                position,
+                heading,
+                STEERING_LOOK_AHEAD,
+                STEERING_CLEARANCE,
+                ignore,
+                &self.obstacles,
+                &self.arena,
+            ) * 2.0
+    }
+}
+
+// Anything solid enough to collide with, except missiles, which are too small and quick to be worth avoiding.
+type ObstacleFilter = (With<Clipping>, Without<Missile>);
+
+/// Records where every solid object is, so that AI ships can steer around them.
+pub fn obstacle_grid_system(
+    q_obstacle: Query<(Entity, &Transform, &CollisionBox), ObstacleFilter>,
+    mut grid: ResMut<ObstacleGrid>,
+    arena: Res<Arena>,
+) {
+    grid.clear(&arena);
+    for (entity, transform, collision_box) in q_obstacle.iter() {
+        grid.insert(Obstacle {
+            entity,
+            position: transform.translation,
+            radius: collision_box.width_radius,
+        });
+    }
+}
+
+// Squads fly in formation until their leader is this close to its target, then split up to attack from their flanks.
+const SQUAD_ENGAGE_RANGE: f32 = 800.0;
+// How far from the target each squad member's flanking position is. This is outside the range squad ships start
+// attacking from, so that they swing all the way around before closing in.
+const SQUAD_FLANK_DISTANCE: f32 = 650.0;
+// Squad members this close to their flanking position are free to close in on their own.
+const SQUAD_FLANK_ARRIVAL: f32 = 100.0;
+
+/// Where a ship closing on its target should head: the spot its squad picked for it if it has one, otherwise
+/// straight at the target.
+fn approach_goal(from: Vec3, target: Vec3, squad: Option<&SquadMember>, arena: &Arena) -> Vec3 {
+    match squad.and_then(|member| member.goal) {
+        Some(goal) => arrive(from, goal, 0.0, 150.0, arena),
+        None => seek(from, target, arena),
+    }
+}
+
+/// Keeps squads together. Followers take their leader's target, hold their formation offsets while the squad travels,
+/// and head for their own flanking positions once it closes in, attacking once they get there. When the leader dies,
+/// the next ship takes over.
+pub fn squad_system(
+    mut commands: Commands,
+    mut q_squad: Query<(Entity, &mut Squad)>,
+    mut q_member: Query<(&mut SquadMember, Option<&AiTarget>)>,
+    targets: Res<TargetCache>,
+    arena: Res<Arena>,
+) {
+    for (squad_entity, mut squad) in q_squad.iter_mut() {
+        squad.members.retain(|member| q_member.contains(*member));
+        if squad.members.is_empty() {
+            commands.entity(squad_entity).despawn();
+            continue;
+        }
+
+        if !squad.members.contains(&squad.leader) {
+            // The next ship in line takes over. Shift everyone's positions so that the formation keeps its shape
+            // around the new leader, and the new leader attacks head on.
+            let new_leader = squad.members[0];
+            let (offset, flank_angle) = {
+                let (leader, _) = q_member.get(new_leader).unwrap();
+                (leader.offset, leader.flank_angle)
+            };
+            for member in &squad.members {
+                let (mut member, _) = q_member.get_mut(*member).unwrap();
+                member.offset -= offset;
+                member.flank_angle -= flank_angle;
+            }
+            squad.leader = new_leader;
+        }
+
+        let leader_target = q_member.get(squad.leader).unwrap().1.map(|t| t.0);
+        let leader_transform = targets.get(squad.leader).map(|info| info.transform);
+        let target_position = leader_target
+            .and_then(|t| targets.get(t))
+            .map(|info| info.transform.translation);
+
+        for member_entity in &squad.members {
+            let (mut member, ai_target) = q_member.get_mut(*member_entity).unwrap();
+            if *member_entity == squad.leader {
+                member.goal = None;
+                continue;
+            }
+            // Everyone fights whoever the leader is fighting.
+            if let Some(target) = leader_target {
+                if ai_target.map(|t| t.0) != Some(target) {
+                    commands.entity(*member_entity).try_insert(AiTarget(target));
+                }
+            }
+            let (Some(leader_transform), Some(target_position)) =
+                (leader_transform, target_position)
+            else {
+                member.goal = None;
+                continue;
+            };
+            let leader_position = leader_transform.translation;
+            member.goal = if wrapped_distance(leader_position, target_position, &arena)
+                > SQUAD_ENGAGE_RANGE
+            {
+                // Hold formation, with offsets turned to match the leader's heading.
+                member.flanked = false;
+                Some(leader_position + leader_transform.rotation * member.offset.extend(0.0))
+            } else if member.flanked {
+                None
+            } else {
+                // Swing around the target to attack from a different side than the leader.
+                let line_of_attack = wrapped_direction(target_position, leader_position, &arena);
+                let flank = target_position
+                    + Quat::from_rotation_z(member.flank_angle)
+                        * line_of_attack
+                        * SQUAD_FLANK_DISTANCE;
+                let arrived = targets.get(*member_entity).is_some_and(|info| {
+                    wrapped_distance(info.transform.translation, flank, &arena)
+                        < SQUAD_FLANK_ARRIVAL
+                });
+                member.flanked = arrived;
+                (!arrived).then_some(flank)
+            };
+        }
+    }
+}
+
+/// Everything the target selection system needs to know about a ship that might be targeted.
+#[derive(WorldQuery)]
+pub struct TargetCandidate {
+    entity: Entity,
+    transform: &'static Transform,
+    health: &'static Health,
+    entity_type: &'static EntityType,
+    velocity: Option<&'static Velocity>,
+    faction: Option<&'static Faction>,
+}
+
+// Ships that pick their own targets: those with a TargetPolicy, and every enemy.
+type TargetSeeker<'a> = (
+    Entity,
+    &'a Transform,
+    Option<&'a TargetPolicy>,
+    Has<Enemy>,
+    Option<&'a Faction>,
+    Option<&'a AiTarget>,
+    Option<&'a mut PendingTarget>,
+    Option<&'a SquadMember>,
+);
+type SeekerFilter = Or<(With<TargetPolicy>, With<Enemy>)>;
+
+/// Who counts as hostile, how quickly enemies react to a new target, and which squads ships take their targets from.
+#[derive(SystemParam)]
+pub struct TargetRules<'w, 's> {
+    factions: Res<'w, FactionTable>,
+    difficulty: Res<'w, Difficulty>,
+    squads: Query<'w, 's, &'static Squad>,
+}
+
+/// Records where every targetable ship is, then has every ship that picks its own targets choose the hostile ship
+/// that best fits its TargetPolicy as its AiTarget. Enemies without a policy go for the nearest hostile ship.
+/// Whether a ship is hostile comes from the faction table.
+/// Enemies take the difficulty's reaction delay to notice a better target, and keep their old one in the meantime.
+/// Squad followers are left alone, since squad_system hands them their leader's target.
+pub fn ai_target_selection_system(
+    mut commands: Commands,
+    mut q_seeker: Query<TargetSeeker, SeekerFilter>,
+    q_candidate: Query<TargetCandidate>,
+    mut cache: ResMut<TargetCache>,
+    arena: Res<Arena>,
+    rules: TargetRules,
+    time: Res<Time>,
+) {
+    let difficulty = &rules.difficulty;
+    cache.targets.clear();
+    for candidate in q_candidate.iter() {
+        if *candidate.entity_type == EntityType::Ship {
+            cache.targets.insert(
+                candidate.entity,
+                TargetInfo {
+                    transform: *candidate.transform,
+                    velocity: candidate.velocity.map_or(Vec3::ZERO, |v| v.velocity),
+                },
+            );
+        }
+    }
+
+    for (
+        seeker,
+        seeker_transform,
+        policy,
+        seeker_is_enemy,
+        seeker_faction,
+        current,
+        pending,
+        squad_member,
+    ) in q_seeker.iter_mut()
+    {
+        let follower = squad_member.is_some_and(|member| {
+            rules
+                .squads
+                .get(member.squad)
+                .is_ok_and(|squad| squad.leader != seeker)
+        });
+        if follower {
+            continue;
+        }
+        // Lower scores are better.
+        let score = |c: &TargetCandidateItem| {
+            let distance = wrapped_distance(
+                seeker_transform.translation,
+                c.transform.translation,
+                &arena,
+            );
+            match policy.copied().unwrap_or(TargetPolicy::Nearest) {
+                TargetPolicy::Nearest => distance,
+                // Break ties between equally healthy ships by distance.
+                TargetPolicy::LowestHealth => c.health.value * 10000.0 + distance,
+                TargetPolicy::MostThreatening => {
+                    let facing = c
+                        .transform
+                        .up()
+                        .dot(wrapped_direction(
+                            c.transform.translation,
+                            seeker_transform.translation,
+                            &arena,
+                        ))
+                        .max(0.0);
+                    -(facing + 0.5) / (distance + 100.0)
+                }
+            }
+        };
+        let best = q_candidate
+            .iter()
+            .filter(|c| {
+                c.entity != seeker
+                    && *c.entity_type == EntityType::Ship
+                    && rules
+                        .factions
+                        .hostile(seeker_faction.copied(), c.faction.copied())
+            })
+            .min_by(|a, b| score(a).total_cmp(&score(b)));
+        match best {
+            Some(target) if current.is_some_and(|c| c.0 == target.entity) => {
+                if pending.is_some() {
+                    commands.entity(seeker).remove::<PendingTarget>();
+                }
+            }
+            Some(target) if !seeker_is_enemy || difficulty.reaction_delay <= 0.0 => {
+                commands.entity(seeker).try_insert(AiTarget(target.entity));
+            }
+            Some(target) => match pending {
+                Some(mut pending) if pending.target == target.entity => {
+                    if pending.timer.tick(time.delta()).finished() {
+                        commands
+                            .entity(seeker)
+                            .try_insert(AiTarget(target.entity))
+                            .remove::<PendingTarget>();
+                    }
+                }
+                _ => {
+                    commands.entity(seeker).try_insert(PendingTarget {
+                        target: target.entity,
+                        timer: Timer::from_seconds(difficulty.reaction_delay, TimerMode::Once),
+                    });
+                }
+            },
+            None => {
+                // Only write when there is something to clear, to avoid needless change detection.
+                if current.is_some() || pending.is_some() {
+                    commands
+                        .entity(seeker)
+                        .remove::<AiTarget>()
+                        .remove::<PendingTarget>();
+                }
+            }
+        }
+    }
+}
+
 pub fn turret_ai_system(
     mut commands: Commands,
     mut q_enemy: Query<
-        (&mut Ship, &mut Transform, &Velocity),
+        (
+            Entity,
+            &mut Ship,
+            &mut Transform,
+            &Velocity,
+            &Faction,
+            Option<&Accuracy>,
+            Option<&AiTarget>,
+        ),
         (With<Enemy>, With<TurretAI>, Without<Player>),
     >,
-    q_player: Query<(&Transform), (With<Player>, Without<Enemy>)>,
+    targets: Res<TargetCache>,
+    arena: Res<Arena>,
+    mut impulse_writer: EventWriter<ImpulseEvent>,
     asset_server: Res<AssetServer>,
+    time: Res<Time>,
 ) {
-    // Simple turret AI. Turn toward the player, and fire repeatedly.
-    for (mut enemy_ship, mut enemy_transform, vel) in q_enemy.iter_mut() {
-        if let Ok(player_transform) = q_player.get_single() {
-            // Calculate the angle between the enemy and the player.
-            let y = player_transform.translation.y - enemy_transform.translation.y;
-            let x = player_transform.translation.x - enemy_transform.translation.x;
-            let target_angle = atan2f(y, x);
-
-            let angle_between = enemy_transform
-                .rotation
-                .angle_between(Quat::from_rotation_z(target_angle))
-                - PI / 2.0;
+    // Simple turret AI. Turn toward where the player is headed, and fire repeatedly.
+    for (enemy_entity, mut enemy_ship, mut enemy_transform, vel, faction, accuracy, ai_target) in
+        q_enemy.iter_mut()
+    {
+        if let Some(target) = ai_target.and_then(|t| targets.get(t.0)) {
+            // Calculate the angle between the enemy and where it should aim to hit the player.
+            let angle_between = lead_angle(
+                &enemy_transform,
+                vel.velocity.length(),
+                &enemy_ship.primary_weapon,
+                target,
+                accuracy,
+                time.delta_seconds(),
+                &arena,
+            );
 
             turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
 
             if (-0.15 < angle_between)
                 && (angle_between < 0.15)
-                && enemy_ship.primary_weapon.cd_timer.finished()
+                && enemy_ship.primary_weapon.ready()
             {
                 // ######### FIRE! ##########
                 // The projectile's transform should originate from the firing ship.
@@ -69,18 +439,26 @@ pub fn turret_ai_system(
                 projectile_transform.translation += enemy_transform.up() * 75.0 * GLOBAL_RESCALE_V;
                 // Ensure that it is rotated in a way that aligns with the firing ship.
                 projectile_transform.rotation = enemy_transform.rotation.clone();
-                commands.spawn((
-                    SpriteBundle {
-                        transform: projectile_transform,
-                        texture: asset_server.load(enemy_ship.primary_weapon.sprite_path.clone()),
-                        ..default()
-                    },
-                    // The Projectile is granted value's from the ship's primary_weapon component.
-                    // This depends on the type of projectile the cannon fires.
-                    enemy_ship
-                        .primary_weapon
-                        .fire(projectile_transform.up(), vel.velocity.length()),
-                ));
+                let weapon = &mut enemy_ship.primary_weapon;
+                let shots = weapon.fire_pattern(&projectile_transform, vel.velocity.length());
+                // Firing pushes the ship back.
+                impulse_writer.send(ImpulseEvent {
+                    target: enemy_entity,
+                    impulse: recoil(&shots),
+                });
+                for (shot_transform, projectile) in shots {
+                    commands.spawn((
+                        SpriteBundle {
+                            transform: shot_transform,
+                            texture: asset_server.load(weapon.sprite_path.clone()),
+                            ..default()
+                        },
+                        // The Projectile is granted value's from the ship's primary_weapon component.
+                        // This depends on the type of projectile the cannon fires.
+                        projectile,
+                        *faction,
+                    ));
+                }
             }
         }
     }
@@ -90,104 +468,147 @@ pub fn speedy_ai_system(
     mut commands: Commands,
     mut q_enemy: Query<
         (
+            Entity,
             &mut Ship,
             &mut Transform,
             &mut Velocity,
-            &mut AITimer,
-            &mut AITimer2,
+            &mut StateMachine,
             &Mass,
             &Thruster,
+            &Faction,
+            Option<&Accuracy>,
+            Option<&SquadMember>,
+            Option<&AiTarget>,
         ),
         (With<Enemy>, With<SpeedyAI>, Without<Player>),
     >,
-    q_player: Query<(&Transform), (With<Player>, Without<Enemy>)>,
+    surroundings: Surroundings,
+    mut impulse_writer: EventWriter<ImpulseEvent>,
     asset_server: Res<AssetServer>,
     time: Res<Time>,
 ) {
+    let (targets, arena) = (&surroundings.targets, &surroundings.arena);
     // The intended behavior of the "speedy" enemy is to fly into mid-range of the player.
     // Once mid-range, alternatively fire on the player and fly away to change position.
-    // Can be viewed as a FSM.
-    // State 1: Fly toward player.
-    // State 2: Turn and Fire at Player.
-    // State 3: Turn and briefly engage thrusters to reposition.
-    // If far from player, enter state 1. Otherwiser alternate states 2 and 3 every few seconds.
+    // The states and when to switch between them are defined by load_speedy_ai.
 
     for (
+        enemy_entity,
         mut enemy_ship,
         mut enemy_transform,
         mut vel,
-        mut ai_timer,
-        mut ai_timer2,
+        mut brain,
         mass,
         thruster,
+        faction,
+        accuracy,
+        squad,
+        ai_target,
     ) in q_enemy.iter_mut()
     {
-        if let Ok(player_transform) = q_player.get_single() {
-            // Calculate the distance between the enemy and the player.
-            let distance_between = enemy_transform
-                .translation
-                .distance(player_transform.translation);
-
-            // Calculate the angle between the enemy and the player.
-            let y = player_transform.translation.y - enemy_transform.translation.y;
-            let x = player_transform.translation.x - enemy_transform.translation.x;
-            let target_angle = atan2f(y, x);
-
-            let angle_between = enemy_transform
-                .rotation
-                .angle_between(Quat::from_rotation_z(target_angle))
-                - PI / 2.0;
-
-            // If we are too far from player, move toward the player by turning and engaging thruster.
-            if distance_between > 500.0 {
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
-                let acceleration = enemy_transform.up() * thruster.force / mass.value;
-                vel.velocity += acceleration * time.delta_seconds();
-                // There should be a global max speed and an individual max speed.
-                // For now instead of dealing with it, just put in a reasonable literal.
-                if vel.velocity.length() > MAX_SPEED {
-                    vel.velocity = vel.velocity.clamp_length_max(MAX_SPEED)
+        let Some(ai_target) = ai_target else {
+            continue;
+        };
+        if let Some(target) = targets.get(ai_target.0) {
+            let target_transform = &target.transform;
+            let senses = Senses {
+                distance: wrapped_distance(
+                    enemy_transform.translation,
+                    target_transform.translation,
+                    arena,
+                ),
+                angle: angle_between(&enemy_transform, target_transform, arena),
+            };
+
+            match brain.update(time.delta(), &senses, &mut enemy_transform, &mut vel) {
+                "approach" => {
+                    // Move toward the player, or wherever our squad wants us, weaving around anything in the way.
+                    let goal = approach_goal(
+                        enemy_transform.translation,
+                        target_transform.translation,
+                        squad,
+                        arena,
+                    );
+                    let desired = surroundings.cruise(
+                        goal,
+                        &enemy_transform,
+                        &vel,
+                        &[enemy_entity, ai_target.0],
+                    );
+                    steer(
+                        &mut enemy_transform,
+                        &mut vel,
+                        desired,
+                        enemy_ship.turn_speed,
+                        thruster.force / mass.value,
+                        time.delta_seconds(),
+                    );
                 }
-            } else if !ai_timer.cd_timer.finished() {
-                ai_timer.cd_timer.tick(time.delta());
-                // Enemy is close enough to the player and is transitioning to state 2. Turn and fire on player.
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
+                "attack" => {
+                    let aim = lead_angle(
+                        &enemy_transform,
+                        vel.velocity.length(),
+                        &enemy_ship.primary_weapon,
+                        target,
+                        accuracy,
+                        time.delta_seconds(),
+                        arena,
+                    );
+                    turn_toward(&mut enemy_transform, enemy_ship.turn_speed, aim);
 
-                if enemy_ship.primary_weapon.cd_timer.finished() {
-                    let mut projectile_transform = Transform::from_xyz(
-                        enemy_transform.translation.x,
-                        enemy_transform.translation.y,
-                        0.0,
-                    )
-                    .with_scale(GLOBAL_RESCALE_V);
-                    // Modify it a little so that it originates from just in front of the firing ship.
-                    projectile_transform.translation +=
-                        enemy_transform.up() * 75.0 * GLOBAL_RESCALE_V;
-                    // Ensure that it is rotated in a way that aligns with the firing ship.
-                    projectile_transform.rotation = enemy_transform.rotation.clone();
-                    commands.spawn((
-                        SpriteBundle {
-                            transform: projectile_transform,
-                            texture: asset_server.load(&enemy_ship.primary_weapon.sprite_path),
-                            ..default()
-                        },
-                        enemy_ship
-                            .primary_weapon
-                            .fire(enemy_transform.up(), vel.velocity.length()),
-                    ));
+                    if enemy_ship.primary_weapon.ready() {
+                        let mut projectile_transform = Transform::from_xyz(
+                            enemy_transform.translation.x,
+                            enemy_transform.translation.y,
+                            0.0,
+                        )
+                        .with_scale(GLOBAL_RESCALE_V);
+                        // Modify it a little so that it originates from just in front of the firing ship.
+                        projectile_transform.translation +=
+                            enemy_transform.up() * 75.0 * GLOBAL_RESCALE_V;
+                        // Ensure that it is rotated in a way that aligns with the firing ship.
+                        projectile_transform.rotation = enemy_transform.rotation.clone();
+                        let weapon = &mut enemy_ship.primary_weapon;
+                        let shots =
+                            weapon.fire_pattern(&projectile_transform, vel.velocity.length());
+                        // Firing pushes the ship back.
+                        impulse_writer.send(ImpulseEvent {
+                            target: enemy_entity,
+                            impulse: recoil(&shots),
+                        });
+                        for (shot_transform, projectile) in shots {
+                            commands.spawn((
+                                SpriteBundle {
+                                    transform: shot_transform,
+                                    texture: asset_server.load(&weapon.sprite_path),
+                                    ..default()
+                                },
+                                projectile,
+                                *faction,
+                            ));
+                        }
+                    }
                 }
-            } else if !ai_timer2.cd_timer.finished() {
-                // Enemy is transitioning to state 3. Activate thrusters and turn a little just to change position.
-                ai_timer2.cd_timer.tick(time.delta());
-                enemy_transform.rotate_z(enemy_ship.turn_speed * 0.5);
-                let acceleration = enemy_transform.up() * thruster.force / mass.value;
-                vel.velocity += acceleration * time.delta_seconds();
-                if vel.velocity.length() > MAX_SPEED {
-                    vel.velocity = vel.velocity.clamp_length_max(MAX_SPEED)
+                "reposition" => {
+                    // Activate thrusters and turn a little just to change position, without flying into anything.
+                    let goal =
+                        Quat::from_rotation_z(enemy_ship.turn_speed * 10.0) * enemy_transform.up();
+                    let desired = surroundings.cruise(
+                        goal,
+                        &enemy_transform,
+                        &vel,
+                        &[enemy_entity, ai_target.0],
+                    );
+                    steer(
+                        &mut enemy_transform,
+                        &mut vel,
+                        desired,
+                        enemy_ship.turn_speed * 0.5,
+                        thruster.force / mass.value,
+                        time.delta_seconds(),
+                    );
                 }
-            } else {
-                ai_timer.cd_timer.reset();
-                ai_timer2.cd_timer.reset();
+                _ => {}
             }
         }
     }
@@ -196,44 +617,97 @@ pub fn speedy_ai_system(
 pub fn drone_ai_system(
     mut commands: Commands,
     mut q_enemy: Query<
-        (&mut Ship, &mut Transform, &mut Velocity, &Mass, &Thruster),
+        (
+            Entity,
+            &mut Ship,
+            &mut Transform,
+            &mut Velocity,
+            &Mass,
+            &Thruster,
+            &Faction,
+            Option<&Accuracy>,
+            Option<&SquadMember>,
+            Option<&AiTarget>,
+        ),
         (With<Enemy>, With<DroneAI>, Without<Player>),
     >,
-    q_player: Query<(&Transform), (With<Player>, Without<Enemy>)>,
+    surroundings: Surroundings,
+    mut impulse_writer: EventWriter<ImpulseEvent>,
     asset_server: Res<AssetServer>,
     time: Res<Time>,
 ) {
+    let (targets, arena) = (&surroundings.targets, &surroundings.arena);
     // The intended behavior of the "drone" enemy is to fly into close-range of the player.
     // Once mid-range, it continuously fires.
 
-    for (mut enemy_ship, mut enemy_transform, mut vel, mass, thruster) in q_enemy.iter_mut() {
-        if let Ok(player_transform) = q_player.get_single() {
+    for (
+        enemy_entity,
+        mut enemy_ship,
+        mut enemy_transform,
+        mut vel,
+        mass,
+        thruster,
+        faction,
+        accuracy,
+        squad,
+        ai_target,
+    ) in q_enemy.iter_mut()
+    {
+        let Some(ai_target) = ai_target else {
+            continue;
+        };
+        if let Some(target) = targets.get(ai_target.0) {
+            let target_transform = &target.transform;
             // Calculate the distance between the enemy and the player.
-            let distance_between = enemy_transform
-                .translation
-                .distance(player_transform.translation);
-
-            // Calculate the angle between the enemy and the player.
-            let y = player_transform.translation.y - enemy_transform.translation.y;
-            let x = player_transform.translation.x - enemy_transform.translation.x;
-            let target_angle = atan2f(y, x);
+            let distance_between = wrapped_distance(
+                enemy_transform.translation,
+                target_transform.translation,
+                arena,
+            );
 
-            let angle_between = enemy_transform
-                .rotation
-                .angle_between(Quat::from_rotation_z(target_angle))
-                - PI / 2.0;
+            // Calculate the angle between the enemy and where it should aim to hit the player.
+            let angle_between = lead_angle(
+                &enemy_transform,
+                vel.velocity.length(),
+                &enemy_ship.primary_weapon,
+                target,
+                accuracy,
+                time.delta_seconds(),
+                arena,
+            );
 
-            turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
-            // If we are too far from player, move toward the player by engaging thruster.
+            // If we are too far from player, close in, easing off as we get into range. Drones swarm, so
+            // keeping clear of each other matters.
             if distance_between > 250.0 {
-                let acceleration = enemy_transform.up() * thruster.force / mass.value;
-                vel.velocity += acceleration * time.delta_seconds();
-                // There should be a global max speed and an individual max speed.
-                // For now instead of dealing with it, just put in a reasonable literal.
-                if vel.velocity.length() > MAX_SPEED {
-                    vel.velocity = vel.velocity.clamp_length_max(MAX_SPEED)
-                }
-            } else if enemy_ship.primary_weapon.cd_timer.finished() {
+                let goal = match squad.and_then(|member| member.goal) {
+                    Some(_) => approach_goal(
+                        enemy_transform.translation,
+                        target_transform.translation,
+                        squad,
+                        arena,
+                    ),
+                    None => arrive(
+                        enemy_transform.translation,
+                        target_transform.translation,
+                        250.0,
+                        300.0,
+                        arena,
+                    ),
+                };
+                let desired =
+                    surroundings.cruise(goal, &enemy_transform, &vel, &[enemy_entity, ai_target.0]);
+                steer(
+                    &mut enemy_transform,
+                    &mut vel,
+                    desired,
+                    enemy_ship.turn_speed,
+                    thruster.force / mass.value,
+                    time.delta_seconds(),
+                );
+                continue;
+            }
+            turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
+            if enemy_ship.primary_weapon.ready() {
                 let mut projectile_transform = Transform::from_xyz(
                     enemy_transform.translation.x,
                     enemy_transform.translation.y,
@@ -244,16 +718,24 @@ pub fn drone_ai_system(
                 projectile_transform.translation += enemy_transform.up() * 75.0 * GLOBAL_RESCALE_V;
                 // Ensure that it is rotated in a way that aligns with the firing ship.
                 projectile_transform.rotation = enemy_transform.rotation.clone();
-                commands.spawn((
-                    SpriteBundle {
-                        transform: projectile_transform,
-                        texture: asset_server.load(&enemy_ship.primary_weapon.sprite_path),
-                        ..default()
-                    },
-                    enemy_ship
-                        .primary_weapon
-                        .fire(enemy_transform.up(), vel.velocity.length()),
-                ));
+                let weapon = &mut enemy_ship.primary_weapon;
+                let shots = weapon.fire_pattern(&projectile_transform, vel.velocity.length());
+                // Firing pushes the ship back.
+                impulse_writer.send(ImpulseEvent {
+                    target: enemy_entity,
+                    impulse: recoil(&shots),
+                });
+                for (shot_transform, projectile) in shots {
+                    commands.spawn((
+                        SpriteBundle {
+                            transform: shot_transform,
+                            texture: asset_server.load(&weapon.sprite_path),
+                            ..default()
+                        },
+                        projectile,
+                        *faction,
+                    ));
+                }
             }
         }
     }
@@ -262,77 +744,85 @@ pub fn drone_ai_system(
 pub fn rammer_ai_system(
     mut q_enemy: Query<
         (
-            &mut Ship,
+            Entity,
+            &Ship,
             &mut Transform,
             &mut Velocity,
-            &mut AITimer,
-            &mut AITimer2,
+            &mut StateMachine,
             &Mass,
             &Thruster,
+            Option<&SquadMember>,
+            Option<&AiTarget>,
         ),
         (With<Enemy>, With<RammerAI>, Without<Player>),
     >,
-    q_player: Query<(&Transform), (With<Player>, Without<Enemy>)>,
+    surroundings: Surroundings,
     time: Res<Time>,
 ) {
+    let (targets, arena) = (&surroundings.targets, &surroundings.arena);
     // The intended behavior of the "rammer" enemy is to fly into close-range of the player.
     // Once close-range, get a brief turbo boost to try to ram into the player, then wait a while
     // to recharge the turbo.
-    // Can be viewed as a FSM.
-    // State 1: Fly toward player.
-    // State 2: Ensure player is in front, then ram.
-    // State 3: Wait for the ramming timer to cooldown.
-    // If far from player, enter state 1. Otherwise alternate states 2 and 3.
+    // The states and when to switch between them are defined by load_rammer_ai.
 
     for (
-        mut enemy_ship,
+        enemy_entity,
+        enemy_ship,
         mut enemy_transform,
         mut vel,
-        mut ai_timer,
-        mut ai_timer2,
+        mut brain,
         mass,
         thruster,
+        squad,
+        ai_target,
     ) in q_enemy.iter_mut()
     {
-        if let Ok(player_transform) = q_player.get_single() {
-            // Calculate the distance between the enemy and the player.
-            let distance_between = enemy_transform
-                .translation
-                .distance(player_transform.translation);
-
-            // Calculate the angle between the enemy and the player.
-            let y = player_transform.translation.y - enemy_transform.translation.y;
-            let x = player_transform.translation.x - enemy_transform.translation.x;
-            let target_angle = atan2f(y, x);
-
-            let angle_between = enemy_transform
-                .rotation
-                .angle_between(Quat::from_rotation_z(target_angle))
-                - PI / 2.0;
+        let Some(ai_target) = ai_target else {
+            continue;
+        };
+        if let Some(target) = targets.get(ai_target.0) {
+            let target_transform = &target.transform;
+            let senses = Senses {
+                distance: wrapped_distance(
+                    enemy_transform.translation,
+                    target_transform.translation,
+                    arena,
+                ),
+                angle: angle_between(&enemy_transform, target_transform, arena),
+            };
 
-            // If we are too far from player, move toward the player by turning and engaging thruster.
-            if distance_between > 250.0 {
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
-                let acceleration = enemy_transform.up() * thruster.force / mass.value;
-                vel.velocity += acceleration * time.delta_seconds();
-                // There should be a global max speed and an individual max speed.
-                // For now instead of dealing with it, just put in a reasonable literal.
-                if vel.velocity.length() > MAX_SPEED {
-                    vel.velocity = vel.velocity.clamp_length_max(MAX_SPEED)
+            match brain.update(time.delta(), &senses, &mut enemy_transform, &mut vel) {
+                "approach" => {
+                    // Close in, steering around everything except the target it means to hit.
+                    let goal = approach_goal(
+                        enemy_transform.translation,
+                        target_transform.translation,
+                        squad,
+                        arena,
+                    );
+                    let desired = surroundings.cruise(
+                        goal,
+                        &enemy_transform,
+                        &vel,
+                        &[enemy_entity, ai_target.0],
+                    );
+                    steer(
+                        &mut enemy_transform,
+                        &mut vel,
+                        desired,
+                        enemy_ship.turn_speed,
+                        thruster.force / mass.value,
+                        time.delta_seconds(),
+                    );
                 }
-            } else if !ai_timer.cd_timer.finished() && libm::fabsf(angle_between) < 0.20 {
-                // Player is close and in front of the rammer. Try ramming.
-                ai_timer.cd_timer.tick(time.delta());
-                // Enemy is close enough to the player and is transitioning to state 2. Turn and fire on player.
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
-                let acceleration = enemy_transform.up() * 10.0 * thruster.force / mass.value;
-                vel.velocity += acceleration * time.delta_seconds();
-            } else if ai_timer2.cd_timer.finished() {
-                ai_timer.cd_timer.reset();
-                ai_timer2.cd_timer.reset();
-            } else {
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
-                ai_timer2.cd_timer.tick(time.delta());
+                "ram" => {
+                    // The player is close and in front of the rammer. Boost straight at them.
+                    turn_toward(&mut enemy_transform, enemy_ship.turn_speed, senses.angle);
+                    let acceleration = enemy_transform.up() * 10.0 * thruster.force / mass.value;
+                    vel.velocity += acceleration * time.delta_seconds();
+                }
+                // While lining up or recharging the rammer just keeps turning toward its target.
+                _ => turn_toward(&mut enemy_transform, enemy_ship.turn_speed, senses.angle),
             }
         }
     }
@@ -341,62 +831,90 @@ pub fn rammer_ai_system(
 pub fn picket_ai_system(
     mut q_enemy: Query<
         (
-            &mut Ship,
+            Entity,
+            &Ship,
             &mut Transform,
             &mut Velocity,
-            &mut AITimer,
+            &mut StateMachine,
             &Mass,
             &Thruster,
+            &Faction,
+            Option<&AiTarget>,
         ),
         (With<Enemy>, With<PicketAI>, Without<Player>),
     >,
-    q_player: Query<(&Transform), (With<Player>, Without<Enemy>)>,
+    surroundings: Surroundings,
     time: Res<Time>,
     mut missile_writer: EventWriter<SpawnGuidedMissileEvent>,
 ) {
+    let (targets, arena) = (&surroundings.targets, &surroundings.arena);
     // The intended behavior of the "picket" enemy is to avoid the player and occasionally launch guided missiles.
     // Once in long-range, fire guided missiles when available. Periodically move a litt.
     // This enemy is a "UFO" shape. To give the appearance of it moving less smoothly than others, it might
     // be given an unusually high drag factor, possibly even 1.0, which would cause it to immediately halt when not
     // subjected to force. In other words, if the drag is 1.0, it cannot drift due to any inertia.
-    // Can be viewed as a FSM.
-    // State 1: Fly away from player until long range..
-    // State 2: Fire guided missile at player.
-    // If far from player, enter state 1. Otherwise alternate states 2 and 3 every few seconds.
+    // The states and when to switch between them are defined by load_picket_ai.
 
-    for (mut enemy_ship, mut enemy_transform, mut vel, mut ai_timer, mass, thruster) in
-        q_enemy.iter_mut()
+    for (
+        enemy_entity,
+        enemy_ship,
+        mut enemy_transform,
+        mut vel,
+        mut brain,
+        mass,
+        thruster,
+        faction,
+        ai_target,
+    ) in q_enemy.iter_mut()
     {
-        if let Ok(player_transform) = q_player.get_single() {
-            // Calculate the distance between the enemy and the player.
-            let distance_between = enemy_transform
-                .translation
-                .distance(player_transform.translation);
-
-            // Calculate the angle between the enemy and the player.
-            let y = player_transform.translation.y - enemy_transform.translation.y;
-            let x = player_transform.translation.x - enemy_transform.translation.x;
-            let target_angle = atan2f(y, x);
-
-            let angle_between = enemy_transform
-                .rotation
-                .angle_between(Quat::from_rotation_z(target_angle))
-                - PI / 2.0;
-
-            // If we are too close to the player, move away from the player by turning and engaging thruster.
-            if distance_between < 600.0 {
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
-                let acceleration = -enemy_transform.up() * thruster.force / mass.value;
-                vel.velocity += acceleration * time.delta_seconds();
-                if vel.velocity.length() > MAX_SPEED {
-                    vel.velocity = vel.velocity.clamp_length_max(MAX_SPEED)
-                }
-            } else if ai_timer.cd_timer.finished() {
-                ai_timer.cd_timer.reset();
-                // Enemy is far enough from the player and is transitioning to state 2. Fire guided missiles.
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
+        let Some(ai_target) = ai_target else {
+            continue;
+        };
+        if let Some(target) = targets.get(ai_target.0) {
+            let target_transform = &target.transform;
+            let senses = Senses {
+                distance: wrapped_distance(
+                    enemy_transform.translation,
+                    target_transform.translation,
+                    arena,
+                ),
+                angle: angle_between(&enemy_transform, target_transform, arena),
+            };
+
+            let goal = match brain.update(time.delta(), &senses, &mut enemy_transform, &mut vel) {
+                // Too close to the player; get away from them.
+                "retreat" => flee(
+                    enemy_transform.translation,
+                    target_transform.translation,
+                    arena,
+                ),
+                // Circle the player at long range while the missile rack reloads.
+                "wait" => orbit(
+                    enemy_transform.translation,
+                    target_transform.translation,
+                    700.0,
+                    true,
+                    arena,
+                ),
+                // Turn to face the target so that the missile leaves the rack pointing at it.
+                _ => seek(
+                    enemy_transform.translation,
+                    target_transform.translation,
+                    arena,
+                ),
+            };
+            let desired = surroundings.cruise(goal, &enemy_transform, &vel, &[enemy_entity]);
+            steer(
+                &mut enemy_transform,
+                &mut vel,
+                desired,
+                enemy_ship.turn_speed,
+                thruster.force / mass.value,
+                time.delta_seconds(),
+            );
 
-                if enemy_ship.primary_weapon.cd_timer.finished() {
+            match brain.state() {
+                "launch" if enemy_ship.primary_weapon.ready() => {
                     let mut projectile_transform = Transform::from_xyz(
                         enemy_transform.translation.x,
                         enemy_transform.translation.y,
@@ -410,11 +928,12 @@ pub fn picket_ai_system(
                     projectile_transform.rotation = enemy_transform.rotation.clone();
                     missile_writer.send(SpawnGuidedMissileEvent {
                         transform: projectile_transform,
+                        target: Some(ai_target.0),
+                        seeker: Seeker::Heat,
+                        faction: Some(*faction),
                     });
                 }
-            } else {
-                turn_toward(&mut enemy_transform, enemy_ship.turn_speed, angle_between);
-                ai_timer.cd_timer.tick(time.delta());
+                _ => {}
             }
         }
     }
@@ -428,6 +947,7 @@ pub fn minelayer_ai_system(
             &mut AITimer,
             &Mass,
             &Thruster,
+            &Faction,
         ),
         (With<Enemy>, With<MineLayerAI>, Without<Player>),
     >,
@@ -437,7 +957,8 @@ pub fn minelayer_ai_system(
     // The intended behavior of the "mine layer" enemy is to slowly cruise across the screen in straight lines while
     // deploying lines of mines.
 
-    for (mut enemy_transform, mut vel, mut ai_timer, mass, thruster) in q_enemy.iter_mut() {
+    for (mut enemy_transform, mut vel, mut ai_timer, mass, thruster, faction) in q_enemy.iter_mut()
+    {
         // Move in a straight line
         let acceleration = enemy_transform.up() * thruster.force / mass.value;
         vel.velocity += acceleration * time.delta_seconds();
@@ -456,8 +977,18 @@ pub fn minelayer_ai_system(
             //Ensure the mine is dropped behind the ship so it doesn't immediately collide!
             projectile_transform.translation += enemy_transform.down() * 75.0 * GLOBAL_RESCALE_V;
 
+            // Most mines are plain explosives, with the occasional nastier surprise.
+            let mine = match thread_rng().gen_range(0..10) {
+                0..=5 => load_standard_mine(),
+                6 => load_emp_mine(),
+                7 | 8 => load_cluster_mine(),
+                _ => load_gravity_mine(),
+            };
             mine_writer.send(SpawnMineEvent {
                 transform: projectile_transform,
+                velocity: Vec3::ZERO,
+                mine,
+                faction: Some(*faction),
             });
         } else {
             ai_timer.cd_timer.tick(time.delta());
@@ -475,13 +1006,15 @@ pub fn guided_missile_ai_system(
             &Thruster,
             &mut Velocity,
             &Mass,
+            Option<&AiTarget>,
         ),
-        (With<Missile>, Without<Player>),
+        With<Missile>,
     >,
-    q_player: Query<&Transform, (With<Player>, Without<Missile>)>,
+    q_target: Query<&Transform, Without<Missile>>,
+    arena: Res<Arena>,
     time: Res<Time>,
 ) {
-    for (missile_entity, mut missile_transform, mut missile, thruster, mut vel, mass) in
+    for (missile_entity, mut missile_transform, mut missile, thruster, mut vel, mass, ai_target) in
         q_missile.iter_mut()
     {
         missile.fuel -= 1.0;
@@ -489,9 +1022,11 @@ pub fn guided_missile_ai_system(
             commands.entity(missile_entity).despawn();
             continue;
         }
-        if let Ok(player_transform) = q_player.get_single() {
-            // The missile self-corrects to point toward the player...
-            let angle_between = angle_between(&missile_transform, &player_transform);
+        // Missiles track the target they were launched at. Missiles without one, or whose target is gone, fly
+        // straight.
+        if let Some(target_transform) = ai_target.and_then(|t| q_target.get(t.0).ok()) {
+            // The missile self-corrects to point toward its target...
+            let angle_between = angle_between(&missile_transform, target_transform, &arena);
             turn_toward(&mut missile_transform, missile.turn_speed, angle_between);
 
             // ...and constantly fires its thruster until collision (or running out of fuel).
@@ -504,93 +1039,641 @@ pub fn guided_missile_ai_system(
     }
 }
 
-pub fn boss_ai_system(
+/// Gives each guided missile that comes within range of a decoy one chance to be fooled by it. The chance depends
+/// on the missile's seeker and the kind of decoy. A fooled missile chases the decoy instead of its target, and
+/// flies off harmlessly once the decoy burns out.
+pub fn decoy_system(
     mut commands: Commands,
-    mut q_enemy: Query<
-        (&mut Transform, &mut AITimer, &mut AITimer2),
-        (With<Enemy>, With<BossAI>, Without<Player>),
-    >,
-    q_player: Query<(&Transform), (With<Player>, Without<Enemy>)>,
-    mut missile_writer: EventWriter<SpawnGuidedMissileEvent>,
+    mut q_decoy: Query<(Entity, &mut Decoy, &Transform)>,
+    q_missile: Query<(Entity, &Missile, &Transform), With<Thruster>>,
+    arena: Res<Arena>,
+) {
+    let mut rng = thread_rng();
+    for (decoy_entity, mut decoy, decoy_transform) in q_decoy.iter_mut() {
+        for (missile_entity, missile, missile_transform) in q_missile.iter() {
+            if decoy.checked.contains(&missile_entity)
+                || wrapped_distance(
+                    decoy_transform.translation,
+                    missile_transform.translation,
+                    &arena,
+                ) > decoy.radius
+            {
+                continue;
+            }
+            decoy.checked.push(missile_entity);
+            if rng.gen::<f32>() < missile.seeker.decoy_chance(decoy.kind) {
+                commands
+                    .entity(missile_entity)
+                    .try_insert(AiTarget(decoy_entity));
+            }
+        }
+    }
+}
+
+// A projectile that might home in on something, and the side it was fired for.
+type HomingProjectile<'a> = (
+    &'a Projectile,
+    &'a mut Transform,
+    &'a mut Velocity,
+    Option<&'a HomingTarget>,
+    Option<&'a Faction>,
+);
+type HomingCandidateFilter = (With<Ship>, Without<Projectile>);
+
+/// Steers projectiles whose type homes in on targets (see ProjectileBehavior). Projectiles with a HomingTarget
+/// track it. Otherwise they go for the nearest ship in front of them that is hostile to the projectile's faction.
+/// Only ships ahead of the projectile are considered, so it won't turn back on the ship that fired it.
+pub fn homing_projectile_system(
+    mut q_projectile: Query<HomingProjectile>,
+    q_ship: Query<(&Transform, Option<&Faction>), HomingCandidateFilter>,
+    q_target: Query<&Transform, Without<Projectile>>,
+    arena: Res<Arena>,
+    factions: Res<FactionTable>,
+) {
+    for (projectile, mut projectile_transform, mut vel, homing_target, faction) in
+        q_projectile.iter_mut()
+    {
+        let Some(turn_speed) = projectile.projectile_type.behavior().homing_turn_speed else {
+            continue;
+        };
+        if let Some(homing_target) = homing_target {
+            // A locked target that no longer exists leaves the projectile flying straight.
+            if let Ok(target_transform) = q_target.get(homing_target.0) {
+                let angle_between = angle_between(&projectile_transform, target_transform, &arena);
+                turn_toward(&mut projectile_transform, turn_speed, angle_between);
+                vel.velocity = projectile_transform.up() * vel.velocity.length();
+            }
+            continue;
+        }
+        let forward = projectile_transform.up();
+        let target = q_ship
+            .iter()
+            .filter(|(t, ship_faction)| {
+                factions.hostile(faction.copied(), ship_faction.copied())
+                    && forward.dot(wrapped_direction(
+                        projectile_transform.translation,
+                        t.translation,
+                        &arena,
+                    )) > 0.5
+            })
+            .map(|(t, _)| t)
+            .min_by(|a, b| {
+                let a_dist =
+                    wrapped_distance(projectile_transform.translation, a.translation, &arena);
+                let b_dist =
+                    wrapped_distance(projectile_transform.translation, b.translation, &arena);
+                a_dist.total_cmp(&b_dist)
+            });
+        if let Some(target_transform) = target {
+            let angle_between = angle_between(&projectile_transform, target_transform, &arena);
+            turn_toward(&mut projectile_transform, turn_speed, angle_between);
+            // Projectiles don't have thrusters, so they keep their speed and just change direction.
+            vel.velocity = projectile_transform.up() * vel.velocity.length();
+        }
+    }
+}
+
+/// Fires point-defense weapons at the nearest incoming missile within range. A missile is incoming if it is
+/// tracking this ship. Point-defense shots are on the ship's side, so they pass through its allies.
+pub fn point_defense_system(
+    mut commands: Commands,
+    mut q_ship: Query<(
+        Entity,
+        &mut PointDefense,
+        &Transform,
+        &Velocity,
+        Option<&Faction>,
+    )>,
+    q_missile: Query<(&Transform, &AiTarget), With<Missile>>,
     asset_server: Res<AssetServer>,
-    time: Res<Time>,
+    arena: Res<Arena>,
 ) {
-    // The intended behavior of the "speedy" enemy is to fly into mid-range of the player.
-    // Once mid-range, alternatively fire on the player and fly away to change position.
-    // Can be viewed as a FSM.
-    // State 1: Wait.
-    // State 2: Launch a volley of guided missiles.
-    // State 3: Launch a volley of drones.
-    // State 4: Spray Lasers in a sinusoidal pattern
-    // Randomly enter a state whenever ai_timer is finished.
-
-    for (mut enemy_transform, mut ai_timer, mut ai_timer2) in q_enemy.iter_mut() {
-        // Force the boss's position to remain constant. This is easier than refactoring physics to accommodate this.
-        enemy_transform.translation = Vec3 {
-            x: 100.0,
-            y: 950.0,
-            z: 0.0,
+    for (ship_entity, mut point_defense, ship_transform, vel, faction) in q_ship.iter_mut() {
+        if !point_defense.weapon.ready() {
+            continue;
+        }
+        let incoming = q_missile
+            .iter()
+            .filter(|(_, ai_target)| ai_target.0 == ship_entity)
+            .map(|(t, _)| wrapped_delta(ship_transform.translation, t.translation, &arena))
+            .filter(|delta| delta.truncate().length() < point_defense.range)
+            .min_by(|a, b| a.length().total_cmp(&b.length()));
+        let Some(delta) = incoming else {
+            continue;
         };
 
-        if let Ok(_) = q_player.get_single() {
-            if ai_timer.cd_timer.finished() {
-                ai_timer.cd_timer.reset();
-                let mut projectile_transform = Transform::from_xyz(
-                    enemy_transform.translation.x,
-                    enemy_transform.translation.y,
-                    0.0,
-                )
-                .with_scale(GLOBAL_RESCALE_V);
+        // Aim straight at the missile.
+        let aim = atan2f(delta.y, delta.x);
+        let mut projectile_transform = Transform::from_xyz(
+            ship_transform.translation.x,
+            ship_transform.translation.y,
+            0.0,
+        )
+        .with_scale(GLOBAL_RESCALE_V * 0.5)
+        .with_rotation(Quat::from_rotation_z(aim - PI / 2.0));
+        projectile_transform.translation += projectile_transform.up() * 75.0 * GLOBAL_RESCALE_V;
+        let weapon = &mut point_defense.weapon;
+        for (shot_transform, projectile) in
+            weapon.fire_pattern(&projectile_transform, vel.velocity.length())
+        {
+            let mut shot = commands.spawn((
+                SpriteBundle {
+                    transform: shot_transform,
+                    texture: asset_server.load(&weapon.sprite_path),
+                    ..default()
+                },
+                projectile,
+            ));
+            if let Some(faction) = faction {
+                shot.insert(*faction);
+            }
+        }
+    }
+}
 
-                // Modify it a little so that it originates from just in front of the firing ship.
-                projectile_transform.translation += enemy_transform.up() * 50.0 * GLOBAL_RESCALE_V
-                    + Vec3 {
-                        x: 160.0,
-                        y: -50.0,
-                        z: 0.0,
-                    };
-                // Ensure that it is rotated in a way that aligns with the firing ship.
-                projectile_transform.rotation = enemy_transform.rotation.clone();
-                missile_writer.send(SpawnGuidedMissileEvent {
-                    transform: projectile_transform,
-                });
-                // Modify it a little so that it originates from just in front of the firing ship.
-                projectile_transform.translation += enemy_transform.up() * 50.0 * GLOBAL_RESCALE_V
-                    + Vec3 {
-                        x: -130.0,
-                        y: -90.0,
-                        z: 0.0,
-                    };
-                // Ensure that it is rotated in a way that aligns with the firing ship.
-                projectile_transform.rotation = enemy_transform.rotation.clone();
-                missile_writer.send(SpawnGuidedMissileEvent {
-                    transform: projectile_transform,
-                });
-            } else {
-                ai_timer.cd_timer.tick(time.delta());
+// A boss, and everything it needs to run its fight.
+type BossBrain<'a> = (
+    Entity,
+    &'a mut Ship,
+    &'a mut Transform,
+    &'a mut Velocity,
+    &'a Health,
+    &'a mut BossPhases,
+    &'a Faction,
+    Option<&'a mut BeamWeapon>,
+    Option<&'a AiTarget>,
+);
+
+// Where a boss's drones launch from, measured from its center.
+const BOSS_DRONE_LAUNCH_DISTANCE: f32 = 260.0;
+const BOSS_DRONE_WAVE: usize = 3;
+
+/// Runs each boss through its phases. The boss holds its position, or drifts toward its target in phases that allow
+/// it, and carries its parts along with it. While any of its parts survive its core is shielded and it can't move on
+/// from its first phase. After that, each phase starts once the boss's health drops below its threshold, and is
+/// announced with a BossPhaseEvent. Each phase's attack patterns are used whenever their cooldowns run out.
+pub fn boss_ai_system(
+    mut q_boss: Query<BossBrain, (With<BossAI>, Without<BossPart>)>,
+    mut q_part: Query<(Entity, &mut Transform, &BossPart), Without<BossAI>>,
+    mut outputs: BehaviorOutputs,
+    behaviors: Res<BehaviorLibrary>,
+    mut phase_writer: EventWriter<BossPhaseEvent>,
+    surroundings: Surroundings,
+    time: Res<Time>,
+) {
+    let (targets, arena) = (&surroundings.targets, &surroundings.arena);
+
+    // Parts go down with their boss.
+    for (part, _, info) in q_part.iter() {
+        if !q_boss.contains(info.boss) {
+            outputs.commands.entity(part).despawn();
+        }
+    }
+
+    for (
+        boss,
+        mut ship,
+        mut transform,
+        mut velocity,
+        health,
+        mut fight,
+        faction,
+        beam,
+        ai_target,
+    ) in q_boss.iter_mut()
+    {
+        let fight = &mut *fight;
+        // Parts that have been destroyed no longer shield the core.
+        fight.parts.retain(|part| q_part.contains(*part));
+
+        let health_left = health.value / fight.max_health;
+        while let Some(next) = fight.phases.get(fight.current + 1) {
+            if fight.shielded() || health_left > next.health_threshold {
+                break;
             }
-            if ai_timer2.cd_timer.finished() {
-                ai_timer2.cd_timer.reset();
-                commands.spawn((
-                    SpriteBundle {
-                        transform: Transform::from_xyz(1500.0, 50.0, 0.0)
+            fight.current += 1;
+            phase_writer.send(BossPhaseEvent {
+                boss,
+                position: transform.translation,
+                name: fight.phase().name,
+                music: fight.phase().music,
+            });
+        }
+
+        let target = ai_target.and_then(|t| targets.get(t.0));
+        let anchor = *fight.anchor.get_or_insert(transform.translation);
+        let phase = &mut fight.phases[fight.current];
+        if phase.drift_speed > 0.0 {
+            // Once it starts drifting, the boss no longer holds its position.
+            fight.anchor = None;
+            velocity.velocity = match target {
+                Some(target) => {
+                    let angle = angle_between(&transform, &target.transform, arena);
+                    turn_toward(&mut transform, ship.turn_speed, angle);
+                    wrapped_direction(transform.translation, target.transform.translation, arena)
+                        * phase.drift_speed
+                }
+                None => Vec3::ZERO,
+            };
+        } else {
+            // Hold the boss in place. This is easier than refactoring physics to accommodate this.
+            transform.translation = anchor;
+            velocity.velocity = Vec3::ZERO;
+        }
+
+        for part in fight.parts.iter() {
+            if let Ok((_, mut part_transform, info)) = q_part.get_mut(*part) {
+                part_transform.translation =
+                    wrap_position(transform.translation + info.offset.extend(0.0), arena);
+            }
+        }
+
+        if let Some(mut beam) = beam {
+            beam.firing = target.is_some() && phase.uses(BossAttack::Beam);
+        }
+        // A spray burst stops when the boss loses its target or moves on to a phase without it.
+        if target.is_none() || !phase.uses(BossAttack::Spray) {
+            ship.secondary_weapon.release_trigger();
+        }
+        let Some(ai_target) = ai_target else {
+            continue;
+        };
+
+        for pattern in phase.patterns.iter_mut() {
+            match pattern.attack {
+                BossAttack::Beam => {}
+                BossAttack::Spray => {
+                    // Spray lasers whenever the spray is ready. Its firing pattern sweeps each burst in a sine wave.
+                    if !ship.secondary_weapon.ready() {
+                        continue;
+                    }
+                    let mut projectile_transform =
+                        Transform::from_xyz(transform.translation.x, transform.translation.y, 0.0)
                             .with_scale(GLOBAL_RESCALE_V)
-                            .with_rotation(Quat::from_rotation_z(PI / 4.0)),
-                        texture: asset_server.load("sprites/ships/rammer.png"),
-                        ..default()
-                    },
-                    Velocity {
-                        velocity: Vec3 {
-                            x: 0.0,
-                            y: 0.0,
-                            z: 0.0,
-                        },
-                    },
-                    load_rammer_ship(),
-                ));
+                            .with_rotation(transform.rotation);
+                    projectile_transform.translation += transform.up() * 150.0 * GLOBAL_RESCALE_V;
+                    let weapon = &mut ship.secondary_weapon;
+                    for (shot_transform, projectile) in
+                        weapon.fire_pattern(&projectile_transform, velocity.velocity.length())
+                    {
+                        outputs.commands.spawn((
+                            SpriteBundle {
+                                transform: shot_transform,
+                                texture: outputs.asset_server.load(&weapon.sprite_path),
+                                ..default()
+                            },
+                            projectile,
+                            *faction,
+                        ));
+                    }
+                }
+                attack => {
+                    if !pattern.cooldown.tick(time.delta()).just_finished() {
+                        continue;
+                    }
+                    match attack {
+                        BossAttack::Missiles => {
+                            for part in fight.parts.iter() {
+                                let Ok((_, bay_transform, info)) = q_part.get(*part) else {
+                                    continue;
+                                };
+                                if info.kind != BossPartKind::MissileBay {
+                                    continue;
+                                }
+                                // Launch outward from the bay, clear of the boss's hull.
+                                let outward = info.offset.normalize().extend(0.0);
+                                outputs.missile_writer.send(SpawnGuidedMissileEvent {
+                                    transform: Transform::from_translation(
+                                        bay_transform.translation + outward * 60.0,
+                                    )
+                                    .with_scale(GLOBAL_RESCALE_V)
+                                    .with_rotation(Quat::from_rotation_arc(Vec3::Y, outward)),
+                                    target: Some(ai_target.0),
+                                    seeker: Seeker::Radar,
+                                    faction: Some(*faction),
+                                });
+                            }
+                        }
+                        BossAttack::Rammers => {
+                            // Rammers arrive from the far side of the arena.
+                            let position = wrap_position(
+                                transform.translation + arena.size().extend(0.0) / 2.0,
+                                arena,
+                            );
+                            spawn_ship(
+                                &mut outputs.commands,
+                                &outputs.asset_server,
+                                &behaviors,
+                                "rammer",
+                                position.x,
+                                position.y,
+                                *faction,
+                            );
+                        }
+                        BossAttack::Drones => {
+                            for i in 0..BOSS_DRONE_WAVE {
+                                let angle = i as f32 * 2.0 * PI / BOSS_DRONE_WAVE as f32;
+                                let position = wrap_position(
+                                    transform.translation
+                                        + (Vec2::from_angle(angle) * BOSS_DRONE_LAUNCH_DISTANCE)
+                                            .extend(0.0),
+                                    arena,
+                                );
+                                spawn_ship(
+                                    &mut outputs.commands,
+                                    &outputs.asset_server,
+                                    &behaviors,
+                                    "drone",
+                                    position.x,
+                                    position.y,
+                                    *faction,
+                                );
+                            }
+                        }
+                        BossAttack::Spray | BossAttack::Beam => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An enemy ship driven by a behavior tree.
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct BehaviorAgent {
+    entity: Entity,
+    ship: &'static mut Ship,
+    transform: &'static mut Transform,
+    velocity: &'static mut Velocity,
+    tree: &'static mut BehaviorTree,
+    mass: &'static Mass,
+    thruster: &'static Thruster,
+    faction: &'static Faction,
+    accuracy: Option<&'static Accuracy>,
+    ai_target: Option<&'static AiTarget>,
+    launcher: Option<&'static mut MissileLauncher>,
+}
+
+/// What behavior tree actions, and bosses, can spawn or send.
+#[derive(SystemParam)]
+pub struct BehaviorOutputs<'w, 's> {
+    commands: Commands<'w, 's>,
+    asset_server: Res<'w, AssetServer>,
+    impulse_writer: EventWriter<'w, ImpulseEvent>,
+    missile_writer: EventWriter<'w, SpawnGuidedMissileEvent>,
+}
+
+/// The ship a behavior tree is being ticked for, and what it can see.
+struct BehaviorContext<'a, 'w, 's> {
+    entity: Entity,
+    ship: &'a mut Ship,
+    transform: &'a mut Transform,
+    velocity: &'a mut Velocity,
+    faction: Faction,
+    launcher: Option<&'a mut MissileLauncher>,
+    acceleration: f32,
+    target: Entity,
+    target_position: Vec3,
+    senses: Senses,
+    delta_seconds: f32,
+    surroundings: &'a Surroundings<'a>,
+    outputs: &'a mut BehaviorOutputs<'w, 's>,
+}
+
+impl BehaviorContext<'_, '_, '_> {
+    /// Engages the thruster along the ship's nose. Negative throttle backs the ship up.
+    fn thrust(&mut self, throttle: f32) {
+        let acceleration = self.transform.up() * self.acceleration * throttle;
+        self.velocity.velocity += acceleration * self.delta_seconds;
+        if self.velocity.velocity.length() > MAX_SPEED {
+            self.velocity.velocity = self.velocity.velocity.clamp_length_max(MAX_SPEED)
+        }
+    }
+
+    /// Steers toward `goal`, keeping clear of other ships and obstacles on the way.
+    fn steer_toward(&mut self, goal: Vec3) {
+        let desired = self.surroundings.cruise(
+            goal,
+            self.transform,
+            self.velocity,
+            &[self.entity, self.target],
+        );
+        steer(
+            self.transform,
+            self.velocity,
+            desired,
+            self.ship.turn_speed,
+            self.acceleration,
+            self.delta_seconds,
+        );
+    }
+
+    fn fire(&mut self, slot: usize) -> BtStatus {
+        let weapon = match slot {
+            1 => &mut self.ship.primary_weapon,
+            2 => &mut self.ship.secondary_weapon,
+            3 => &mut self.ship.tertiary_weapon,
+            _ => return BtStatus::Failure,
+        };
+        if !weapon.ready() {
+            return BtStatus::Failure;
+        }
+        let mut projectile_transform = Transform::from_xyz(
+            self.transform.translation.x,
+            self.transform.translation.y,
+            0.0,
+        )
+        .with_scale(GLOBAL_RESCALE_V)
+        .with_rotation(self.transform.rotation);
+        // Modify it a little so that it originates from just in front of the firing ship.
+        projectile_transform.translation += self.transform.up() * 75.0 * GLOBAL_RESCALE_V;
+        let shots = weapon.fire_pattern(&projectile_transform, self.velocity.velocity.length());
+        // Firing pushes the ship back.
+        self.outputs.impulse_writer.send(ImpulseEvent {
+            target: self.entity,
+            impulse: recoil(&shots),
+        });
+        for (shot_transform, projectile) in shots {
+            self.outputs.commands.spawn((
+                SpriteBundle {
+                    transform: shot_transform,
+                    texture: self.outputs.asset_server.load(&weapon.sprite_path),
+                    ..default()
+                },
+                projectile,
+                self.faction,
+            ));
+        }
+        BtStatus::Success
+    }
+
+    fn launch_missile(&mut self) -> BtStatus {
+        match self.launcher.as_mut() {
+            Some(launcher) if launcher.cd_timer.finished() => launcher.cd_timer.reset(),
+            _ => return BtStatus::Failure,
+        }
+        let mut projectile_transform = Transform::from_xyz(
+            self.transform.translation.x,
+            self.transform.translation.y,
+            0.0,
+        )
+        .with_scale(GLOBAL_RESCALE_V)
+        .with_rotation(self.transform.rotation);
+        projectile_transform.translation += self.transform.up() * 75.0 * GLOBAL_RESCALE_V;
+        self.outputs.missile_writer.send(SpawnGuidedMissileEvent {
+            transform: projectile_transform,
+            target: Some(self.target),
+            seeker: Seeker::Heat,
+            faction: Some(self.faction),
+        });
+        BtStatus::Success
+    }
+}
+
+/// Ticks one node of a behavior tree, and any of its children that need to run.
+fn tick_behavior(tree: &mut BehaviorTree, index: usize, ctx: &mut BehaviorContext) -> BtStatus {
+    match tree.nodes[index].clone() {
+        BtNode::Selector(children) => {
+            for (i, child) in children.iter().enumerate() {
+                let status = tick_behavior(tree, *child, ctx);
+                if status != BtStatus::Failure {
+                    // Lower priority branches were interrupted, so they start over next time.
+                    for skipped in &children[i + 1..] {
+                        tree.reset(*skipped);
+                    }
+                    return status;
+                }
+            }
+            BtStatus::Failure
+        }
+        BtNode::Sequence(children) => {
+            for (i, child) in children.iter().enumerate().skip(tree.cursor[index]) {
+                match tick_behavior(tree, *child, ctx) {
+                    BtStatus::Success => continue,
+                    BtStatus::Running => {
+                        tree.cursor[index] = i;
+                        return BtStatus::Running;
+                    }
+                    BtStatus::Failure => {
+                        tree.cursor[index] = 0;
+                        return BtStatus::Failure;
+                    }
+                }
+            }
+            tree.cursor[index] = 0;
+            BtStatus::Success
+        }
+        BtNode::TargetCloserThan(range) => check(ctx.senses.distance <= range),
+        BtNode::TargetFartherThan(range) => check(ctx.senses.distance > range),
+        BtNode::TargetAhead(angle) => check(ctx.senses.angle.abs() < angle),
+        BtNode::TurnToward => {
+            turn_toward(ctx.transform, ctx.ship.turn_speed, ctx.senses.angle);
+            BtStatus::Success
+        }
+        BtNode::Thrust(throttle) => {
+            ctx.thrust(throttle);
+            BtStatus::Success
+        }
+        BtNode::Fire(slot) => ctx.fire(slot),
+        BtNode::KeepDistance(range) => {
+            // Allow some slack so the ship doesn't jitter back and forth around the exact range.
+            if ctx.senses.distance > range * 1.1 {
+                ctx.thrust(1.0);
+            } else if ctx.senses.distance < range * 0.9 {
+                ctx.thrust(-1.0);
+            }
+            BtStatus::Success
+        }
+        BtNode::Flee => {
+            let goal = flee(
+                ctx.transform.translation,
+                ctx.target_position,
+                &ctx.surroundings.arena,
+            );
+            ctx.steer_toward(goal);
+            BtStatus::Success
+        }
+        BtNode::Orbit(radius) => {
+            let goal = orbit(
+                ctx.transform.translation,
+                ctx.target_position,
+                radius,
+                true,
+                &ctx.surroundings.arena,
+            );
+            ctx.steer_toward(goal);
+            BtStatus::Success
+        }
+        BtNode::LaunchMissile => ctx.launch_missile(),
+        BtNode::Wait(seconds) => {
+            tree.elapsed[index] += ctx.delta_seconds;
+            if tree.elapsed[index] >= seconds {
+                tree.elapsed[index] = 0.0;
+                BtStatus::Success
             } else {
-                ai_timer2.cd_timer.tick(time.delta());
+                BtStatus::Running
             }
         }
     }
 }
+
+fn check(condition: bool) -> BtStatus {
+    if condition {
+        BtStatus::Success
+    } else {
+        BtStatus::Failure
+    }
+}
+
+pub fn behavior_tree_ai_system(
+    mut q_enemy: Query<BehaviorAgent, (With<Enemy>, Without<Player>)>,
+    surroundings: Surroundings,
+    time: Res<Time>,
+    mut outputs: BehaviorOutputs,
+) {
+    let (targets, arena) = (&surroundings.targets, &surroundings.arena);
+    // Ships with a BehaviorTree are driven entirely by the tree loaded for them from assets/behaviors/.
+    // Like the other AI, they idle when they have nothing to target.
+    for mut agent in q_enemy.iter_mut() {
+        let Some(ai_target) = agent.ai_target else {
+            continue;
+        };
+        let Some(target) = targets.get(ai_target.0) else {
+            continue;
+        };
+        let senses = Senses {
+            distance: wrapped_distance(
+                agent.transform.translation,
+                target.transform.translation,
+                arena,
+            ),
+            // Trees turn toward their target mostly to shoot at it, so they lead it with their primary weapon.
+            angle: lead_angle(
+                &agent.transform,
+                agent.velocity.velocity.length(),
+                &agent.ship.primary_weapon,
+                target,
+                agent.accuracy,
+                time.delta_seconds(),
+                arena,
+            ),
+        };
+        let mut ctx = BehaviorContext {
+            entity: agent.entity,
+            ship: &mut agent.ship,
+            transform: &mut agent.transform,
+            velocity: &mut agent.velocity,
+            faction: *agent.faction,
+            launcher: agent.launcher.as_deref_mut(),
+            acceleration: agent.thruster.force / agent.mass.value,
+            target: ai_target.0,
+            target_position: target.transform.translation,
+            senses,
+            delta_seconds: time.delta_seconds(),
+            surroundings: &surroundings,
+            outputs: &mut outputs,
+        };
+        tick_behavior(&mut agent.tree, 0, &mut ctx);
+    }
+}
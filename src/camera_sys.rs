@@ -1,43 +1,116 @@
 // use bevy::core_pipeline::bloom::BloomSettings;
 use crate::components::*;
+use crate::events::*;
+use crate::geometry::*;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use rand::prelude::*;
 
 pub fn spawn_camera_system(
     mut commands: Commands,
     mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
 ) {
     // Also setting the resolution to 1600x1000 here so we have more screen to work.
     // Window resizing will probably get moved later on.
     let mut window = window_query.get_single_mut().unwrap();
     window.resolution.set(1600.0, 1000.0);
 
+    // The camera starts centered on the arena, where the player spawns, and follows the player from there.
     commands.spawn((
         Camera2dBundle {
             // camera: Camera {
             //     hdr: true,
             //     ..default()
             // },
-            transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
+            transform: Transform::from_xyz(arena.width / 2.0, arena.height / 2.0, 0.0),
             ..default()
         },
         MainCamera {},
+        FollowCamera {
+            smoothing: 5.0,
+            lead: 40.0,
+            max_lead: 300.0,
+            focus: Vec2::new(arena.width / 2.0, arena.height / 2.0),
+        },
+        CameraShake {
+            trauma: 0.0,
+            decay: 1.5,
+            max_offset: 30.0,
+            max_roll: 0.05,
+            offset: Vec2::ZERO,
+        },
         // BloomSettings::OLD_SCHOOL,
     ));
 }
 
+/// Moves the camera toward a point ahead of the player, keeping the view inside the arena.
+/// Any camera shake is applied on top of the smoothed focus point.
+pub fn follow_camera_system(
+    mut camera_query: Query<
+        (&mut Transform, &mut FollowCamera, Option<&CameraShake>),
+        Without<Player>,
+    >,
+    player_query: Query<(&Transform, &Velocity), With<Player>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
+    time: Res<Time>,
+) {
+    let (Ok((mut camera_transform, mut follow, shake)), Ok(window)) =
+        (camera_query.get_single_mut(), window_query.get_single())
+    else {
+        return;
+    };
+
+    // If there's no player, hold the camera where it is (but keep shaking).
+    if let Ok((player_transform, player_velocity)) = player_query.get_single() {
+        // Lead ahead of the player by where they would be in a few frames, within reason.
+        let lead = (player_velocity.velocity.truncate() * MS_TO_PS * follow.lead)
+            .clamp_length_max(follow.max_lead);
+        let target = player_transform.translation.truncate() + lead;
+        let mut position = follow.focus;
+
+        // If the player just wrapped to the other side of the arena, jump along with them instead of
+        // sweeping the camera across the whole arena.
+        let arena_size = arena.size();
+        let delta = target - position;
+        if delta.x.abs() > arena_size.x / 2.0 {
+            position.x += arena_size.x * delta.x.signum();
+        }
+        if delta.y.abs() > arena_size.y / 2.0 {
+            position.y += arena_size.y * delta.y.signum();
+        }
+
+        // Exponential smoothing, so that the camera eases in the same way regardless of frame rate.
+        let t = 1.0 - (-follow.smoothing * time.delta_seconds()).exp();
+        position = position.lerp(target, t);
+
+        // Keep the view inside the arena. If the arena is smaller than the window on an axis, center it instead.
+        let half_view = Vec2::new(window.width(), window.height()) / 2.0;
+        for axis in 0..2 {
+            position[axis] = if arena_size[axis] > half_view[axis] * 2.0 {
+                position[axis].clamp(half_view[axis], arena_size[axis] - half_view[axis])
+            } else {
+                arena_size[axis] / 2.0
+            };
+        }
+        follow.focus = position;
+    }
+
+    let offset = shake.map(|shake| shake.offset).unwrap_or_default();
+    camera_transform.translation = (follow.focus + offset).extend(camera_transform.translation.z);
+}
+
 pub fn confine_player_movement_system(
     mut player_query: Query<&mut Transform, With<Player>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
 ) {
     if let Ok(mut player_transform) = player_query.get_single_mut() {
-        let window = window_query.get_single().unwrap();
-
         let half_player_size = 32.0;
         let x_min = 0.0 + half_player_size;
-        let x_max = window.width() - half_player_size;
+        let x_max = arena.width - half_player_size;
         let y_min = 0.0 + half_player_size;
-        let y_max = window.height() - half_player_size;
+        let y_max = arena.height - half_player_size;
 
         let mut translation = player_transform.translation;
 
@@ -59,35 +132,88 @@ pub fn confine_player_movement_system(
     }
 }
 
-pub fn wrap_clipping_location_system(
-    mut clipping_query: Query<&mut Transform, With<Clipping>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+/// Decays the camera's trauma and works out this frame's shake. The follow camera applies the offset, this
+/// system applies the roll.
+pub fn camera_shake_system(
+    mut camera_query: Query<(&mut Transform, &mut CameraShake), With<MainCamera>>,
+    settings: Res<Settings>,
+    time: Res<Time>,
 ) {
-    for mut transform in clipping_query.iter_mut() {
-        let window = window_query.get_single().unwrap();
-
-        let x_min = 0.0;
-        let x_max = window.width();
-        let y_min = 0.0;
-        let y_max = window.height();
-
-        let mut translation = transform.translation;
+    let mut rng = thread_rng();
+    for (mut transform, mut shake) in camera_query.iter_mut() {
+        shake.trauma = (shake.trauma - shake.decay * time.delta_seconds()).max(0.0);
+        let amount = shake.trauma.powi(2) * settings.screen_shake_intensity;
+        shake.offset = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0))
+            * shake.max_offset
+            * amount;
+        transform.rotation =
+            Quat::from_rotation_z(shake.max_roll * amount * rng.gen_range(-1.0..=1.0));
+    }
+}
 
-        // Bound the player x position
-        if translation.x < x_min {
-            translation.x = x_max;
-        } else if translation.x > x_max {
-            translation.x = x_min;
+/// Reads CameraImpulseEvent events, adds their trauma to the camera shake, and starts any hit-stop.
+pub fn camera_impulse_system(
+    mut impulse_reader: EventReader<CameraImpulseEvent>,
+    mut camera_query: Query<&mut CameraShake, With<MainCamera>>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    settings: Res<Settings>,
+) {
+    for ev in impulse_reader.read() {
+        for mut shake in camera_query.iter_mut() {
+            shake.trauma = (shake.trauma + ev.trauma).min(1.0);
+        }
+        if settings.hit_stop && ev.hit_stop > 0.0 {
+            // Don't shorten a longer hit-stop that's already running.
+            let remaining = hit_stop
+                .timer
+                .as_ref()
+                .map(|timer| timer.remaining_secs())
+                .unwrap_or(0.0);
+            if ev.hit_stop > remaining {
+                hit_stop.timer = Some(Timer::from_seconds(ev.hit_stop, TimerMode::Once));
+                virtual_time.pause();
+            }
         }
+    }
+}
 
-        // Bound the player y position
-        if translation.y < y_min {
-            translation.y = y_max;
-        } else if translation.y > y_max {
-            translation.y = y_min;
+/// Ends the hit-stop once its timer runs out. The timer is ticked in real time, since virtual time is paused.
+pub fn hit_stop_system(
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+) {
+    if let Some(timer) = hit_stop.timer.as_mut() {
+        timer.tick(real_time.delta());
+        if timer.finished() {
+            hit_stop.timer = None;
+            virtual_time.unpause();
         }
+    }
+}
+
+/// Run condition for systems that step the world once per frame rather than by delta time, like movement and AI
+/// turning. They would keep going through a hit-stop otherwise, since pausing virtual time only stops delta time.
+pub fn hit_stop_inactive(hit_stop: Res<HitStop>) -> bool {
+    hit_stop.timer.is_none()
+}
 
-        transform.translation = translation;
+/// Query filter for entities that live in the arena and wrap around its edges.
+type Wraps = Or<(With<Clipping>, With<Phase>)>;
+
+/// Wraps entities that leave the arena around to the opposite edge. This applies to both Clipping entities
+/// and Phase entities (projectiles), so that everything in the arena lives on the same torus.
+pub fn wrap_clipping_location_system(
+    mut clipping_query: Query<&mut Transform, Wraps>,
+    arena: Res<Arena>,
+) {
+    for mut transform in clipping_query.iter_mut() {
+        // Only write to the transform when it actually leaves the arena, to avoid needless change detection.
+        let wrapped = wrap_position(transform.translation, &arena);
+        if wrapped != transform.translation {
+            transform.translation = wrapped;
+        }
     }
 }
 
@@ -96,11 +222,14 @@ pub fn mouse_world_coords_system(
     mut coords: ResMut<WorldCoords>,
     window_query: Query<&Window, With<PrimaryWindow>>,
     // query to get camera transform
-    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    camera_query: Query<(&Camera, &Transform), With<MainCamera>>,
 ) {
     // get the camera info and transform
     // assuming there is exactly one main camera entity, so Query::single() is OK
+    // The camera moves during Update, so build its GlobalTransform from the current Transform instead of using
+    // last frame's propagated GlobalTransform. The camera has no parent, so the two are equivalent.
     let (camera, camera_transform) = camera_query.single();
+    let camera_transform = GlobalTransform::from(*camera_transform);
 
     // There is only one primary window, so we can similarly get it from the query:
     let window = window_query.single();
@@ -109,7 +238,7 @@ pub fn mouse_world_coords_system(
     // then, ask bevy to convert into world coordinates, and truncate to discard Z
     if let Some(world_position) = window
         .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .and_then(|cursor| camera.viewport_to_world(&camera_transform, cursor))
         .map(|ray| ray.origin.truncate())
     {
         coords.coords = world_position;
@@ -1,5 +1,8 @@
 use crate::traits::*;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::prelude::*;
+use std::time::Duration;
 
 /// This file contains the ECS Components to make the game run in addition to containing
 /// global constants and utility structs.
@@ -41,6 +44,16 @@ pub const KE_TO_DMG: f32 = 0.001;
 
 pub const MAX_SPEED: f32 = 300.0;
 
+// The health the player starts with, and the most a repair pickup can bring them back up to.
+pub const PLAYER_HEALTH: f32 = 500.0;
+
+// The health of the boss's core.
+pub const BOSS_HEALTH: f32 = 5000.0;
+
+// Scales momentum (kg * m/s) before it is applied as an impulse. Like KE_TO_DMG, this lets projectiles keep
+// realistic masses and speeds while still pushing ships that are far heavier than any real spacecraft.
+pub const IMPULSE_SCALE: f32 = 100.0;
+
 // Bevy Components
 
 // Components relating to ships
@@ -69,6 +82,8 @@ pub struct WeaponSystem {
     pub dmg_type: DamageType,
     pub cd_timer: Timer,
     pub dmg: f32,
+    pub pattern: FiringPattern,
+    pub resource: WeaponResource,
 }
 impl Weapon<(Projectile, Phase, Velocity)> for WeaponSystem {
     fn fire(&mut self, vel_dir: Vec3, origin_speed: f32) -> (Projectile, Phase, Velocity) {
@@ -90,6 +105,403 @@ impl Weapon<(Projectile, Phase, Velocity)> for WeaponSystem {
     }
 }
 
+impl WeaponSystem {
+    /// Whether the weapon can fire right now, either because it is off cooldown or because it is partway through
+    /// a burst and the next shot of the burst is due. Weapons that are out of ammo or overheated can't fire.
+    /// Energy is drawn from the firing ship's Reactor, so it is up to the caller to check for it.
+    pub fn ready(&self) -> bool {
+        let timer_ready = if self.pattern.burst_remaining > 0 {
+            self.pattern.burst_timer.finished()
+        } else {
+            self.cd_timer.finished()
+        };
+        timer_ready && self.resource.ready()
+    }
+
+    /// Lets go of the trigger. A burst cut short is dropped rather than picked up where it left off, so the next shot
+    /// waits out the cooldown.
+    pub fn release_trigger(&mut self) {
+        self.pattern.burst_remaining = 0;
+    }
+
+    /// Ticks the weapon's cooldown and burst timers, reloads it, and cools it down.
+    pub fn tick(&mut self, delta: Duration) {
+        self.cd_timer.tick(delta);
+        self.pattern.burst_timer.tick(delta);
+        self.resource.tick(delta);
+    }
+
+    /// The energy each shot draws from the firing ship's Reactor.
+    pub fn energy_cost(&self) -> f32 {
+        match self.resource {
+            WeaponResource::Energy { cost } => cost,
+            _ => 0.0,
+        }
+    }
+
+    /// Fires one shot of the weapon's firing pattern from `muzzle`, which should sit just in front of the firing
+    /// ship and face the way it is firing. Returns the transform and components for each projectile to spawn.
+    pub fn fire_pattern(&mut self, muzzle: &Transform, origin_speed: f32) -> Vec<Shot> {
+        let mut rng = thread_rng();
+        let pattern = &mut self.pattern;
+
+        // Alternating weapons cycle through their barrels one shot at a time. Otherwise every barrel fires at once.
+        let barrels = if pattern.barrels.is_empty() {
+            vec![Vec2::ZERO]
+        } else if pattern.alternate {
+            let barrel = pattern.barrels[pattern.next_barrel % pattern.barrels.len()];
+            pattern.next_barrel = (pattern.next_barrel + 1) % pattern.barrels.len();
+            vec![barrel]
+        } else {
+            pattern.barrels.clone()
+        };
+
+        let sweep_angle = match pattern.sweep {
+            Some(sweep) => {
+                pattern.sweep_phase += sweep.speed;
+                sweep.amplitude * pattern.sweep_phase.sin()
+            }
+            None => 0.0,
+        };
+
+        let mut aims = Vec::new();
+        for barrel in barrels {
+            let translation =
+                muzzle.translation + muzzle.rotation * (barrel.extend(0.0) * muzzle.scale);
+            for pellet in 0..pattern.pellets {
+                // Pellets are fanned out evenly across the spread angle.
+                let spread_angle = if pattern.pellets > 1 {
+                    pattern.spread * (pellet as f32 / (pattern.pellets - 1) as f32 - 0.5)
+                } else {
+                    0.0
+                };
+                let inaccuracy = if pattern.inaccuracy > 0.0 {
+                    rng.gen_range(-pattern.inaccuracy..pattern.inaccuracy)
+                } else {
+                    0.0
+                };
+                let rotation = muzzle.rotation
+                    * Quat::from_rotation_z(sweep_angle + spread_angle + inaccuracy);
+                aims.push(muzzle.with_translation(translation).with_rotation(rotation));
+            }
+        }
+
+        // Start a new burst, or count down the one in progress.
+        if pattern.burst_remaining == 0 {
+            pattern.burst_remaining = pattern.burst_count.saturating_sub(1);
+        } else {
+            pattern.burst_remaining -= 1;
+        }
+        pattern.burst_timer.reset();
+        self.resource.spend();
+
+        aims.into_iter()
+            .map(|aim| (aim, self.fire(aim.up(), origin_speed)))
+            .collect()
+    }
+}
+
+/// Heat that builds up while a weapon fires and bleeds off over time. Reaching max_heat locks the weapon out until it
+/// has fully cooled. Shared by heat-limited WeaponSystems and BeamWeapons.
+#[derive(Clone)]
+pub struct HeatSink {
+    pub heat: f32,
+    pub max_heat: f32,
+    // Heat lost per second.
+    pub cool_rate: f32,
+    pub overheated: bool,
+}
+
+impl HeatSink {
+    /// A cold heat sink.
+    pub fn new(max_heat: f32, cool_rate: f32) -> Self {
+        HeatSink {
+            heat: 0.0,
+            max_heat,
+            cool_rate,
+            overheated: false,
+        }
+    }
+
+    pub fn add(&mut self, amount: f32) {
+        self.heat += amount;
+        if self.heat >= self.max_heat {
+            self.overheated = true;
+        }
+    }
+
+    pub fn cool(&mut self, delta: Duration) {
+        self.heat = (self.heat - self.cool_rate * delta.as_secs_f32()).max(0.0);
+        if self.overheated && self.heat <= 0.0 {
+            self.overheated = false;
+        }
+    }
+
+    /// How close to max_heat the sink is, from 0.0 to 1.0.
+    pub fn fraction(&self) -> f32 {
+        (self.heat / self.max_heat).clamp(0.0, 1.0)
+    }
+}
+
+/// What, besides its cooldown, limits how much a WeaponSystem can fire.
+#[derive(Clone)]
+pub enum WeaponResource {
+    Unlimited,
+    // A magazine of rounds. The weapon reloads automatically once it runs dry, or early if asked to.
+    Ammo {
+        rounds: u32,
+        max_rounds: u32,
+        reloading: bool,
+        reload_timer: Timer,
+    },
+    // Each shot adds heat_per_shot to the weapon's heat sink.
+    Heat {
+        sink: HeatSink,
+        heat_per_shot: f32,
+    },
+    // Each shot draws energy from the firing ship's Reactor.
+    Energy {
+        cost: f32,
+    },
+}
+
+impl WeaponResource {
+    /// A full magazine of `max_rounds` that takes `reload_time` seconds to refill.
+    pub fn ammo(max_rounds: u32, reload_time: f32) -> Self {
+        WeaponResource::Ammo {
+            rounds: max_rounds,
+            max_rounds,
+            reloading: false,
+            reload_timer: Timer::from_seconds(reload_time, TimerMode::Once),
+        }
+    }
+
+    /// A cold heat sink.
+    pub fn heat(max_heat: f32, heat_per_shot: f32, cool_rate: f32) -> Self {
+        WeaponResource::Heat {
+            sink: HeatSink::new(max_heat, cool_rate),
+            heat_per_shot,
+        }
+    }
+
+    fn ready(&self) -> bool {
+        match self {
+            WeaponResource::Ammo {
+                rounds, reloading, ..
+            } => *rounds > 0 && !reloading,
+            WeaponResource::Heat { sink, .. } => !sink.overheated,
+            WeaponResource::Unlimited | WeaponResource::Energy { .. } => true,
+        }
+    }
+
+    fn spend(&mut self) {
+        match self {
+            WeaponResource::Ammo { rounds, .. } => {
+                *rounds = rounds.saturating_sub(1);
+                if *rounds == 0 {
+                    self.reload();
+                }
+            }
+            WeaponResource::Heat {
+                sink,
+                heat_per_shot,
+            } => sink.add(*heat_per_shot),
+            WeaponResource::Unlimited | WeaponResource::Energy { .. } => {}
+        }
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        match self {
+            WeaponResource::Ammo {
+                rounds,
+                max_rounds,
+                reloading,
+                reload_timer,
+            } => {
+                if *reloading {
+                    reload_timer.tick(delta);
+                    if reload_timer.finished() {
+                        *rounds = *max_rounds;
+                        *reloading = false;
+                    }
+                }
+            }
+            WeaponResource::Heat { sink, .. } => sink.cool(delta),
+            WeaponResource::Unlimited | WeaponResource::Energy { .. } => {}
+        }
+    }
+
+    /// Starts reloading, unless the magazine is already full or a reload is underway.
+    pub fn reload(&mut self) {
+        if let WeaponResource::Ammo {
+            rounds,
+            max_rounds,
+            reloading,
+            reload_timer,
+        } = self
+        {
+            if !*reloading && rounds < max_rounds {
+                *reloading = true;
+                reload_timer.reset();
+            }
+        }
+    }
+}
+
+/// A single projectile fired by a WeaponSystem, and where it starts.
+pub type Shot = (Transform, (Projectile, Phase, Velocity));
+
+/// The momentum the firing ship loses by firing `shots`, pointing back the way they came.
+pub fn recoil(shots: &[Shot]) -> Vec3 {
+    shots
+        .iter()
+        .map(|(transform, (projectile, _, _))| -transform.up() * projectile.mass * projectile.speed)
+        .sum()
+}
+
+/// How a WeaponSystem lays out its shots. Each shot fires from every barrel, or from the next barrel in turn if
+/// the barrels alternate. Each barrel fires `pellets` projectiles fanned across the `spread` angle, each nudged by
+/// up to `inaccuracy` radians at random. Holding the trigger fires a burst of `burst_count` shots `burst_delay`
+/// apart before the weapon goes on cooldown. Releasing it partway through ends the burst early.
+#[derive(Clone)]
+pub struct FiringPattern {
+    // Barrel positions relative to the muzzle, in the firing ship's unscaled sprite space. +y is forward.
+    pub barrels: Vec<Vec2>,
+    pub alternate: bool,
+    pub pellets: u32,
+    pub spread: f32,
+    pub inaccuracy: f32,
+    pub burst_count: u32,
+    pub burst_timer: Timer,
+    // Sweeps the aim back and forth across the muzzle's facing from shot to shot.
+    pub sweep: Option<PatternSweep>,
+    pub next_barrel: usize,
+    pub burst_remaining: u32,
+    pub sweep_phase: f32,
+}
+
+impl FiringPattern {
+    /// A pattern firing `burst_count` shots `burst_delay` seconds apart per trigger pull.
+    pub fn burst(burst_count: u32, burst_delay: f32) -> Self {
+        Self {
+            burst_count,
+            burst_timer: Timer::from_seconds(burst_delay, TimerMode::Once),
+            ..default()
+        }
+    }
+}
+
+impl Default for FiringPattern {
+    /// A single barrel firing one projectile straight ahead.
+    fn default() -> Self {
+        Self {
+            barrels: vec![Vec2::ZERO],
+            alternate: false,
+            pellets: 1,
+            spread: 0.0,
+            inaccuracy: 0.0,
+            burst_count: 1,
+            burst_timer: Timer::from_seconds(0.0, TimerMode::Once),
+            sweep: None,
+            next_barrel: 0,
+            burst_remaining: 0,
+            sweep_phase: 0.0,
+        }
+    }
+}
+
+/// A sinusoidal sweep of a weapon's aim. `speed` is how far through the sine wave each shot advances, in radians.
+#[derive(Clone, Copy)]
+pub struct PatternSweep {
+    pub amplitude: f32,
+    pub speed: f32,
+}
+
+/// A ship-wide energy pool that recharges over time. Energy weapons draw from it with each shot, and the ship's
+/// thrusters draw from it while they are firing.
+#[derive(Component)]
+pub struct Reactor {
+    pub energy: f32,
+    pub max_energy: f32,
+    // Energy regained per second.
+    pub recharge_rate: f32,
+    // Energy drawn per second of thrust.
+    pub thrust_drain: f32,
+}
+
+/// Marks the HUD text showing the player's weapons and reactor.
+#[derive(Component)]
+pub struct WeaponHud {}
+
+/// A ship module that automatically shoots down incoming missiles within range.
+#[derive(Component)]
+pub struct PointDefense {
+    pub weapon: WeaponSystem,
+    pub range: f32,
+}
+
+/// A continuous beam weapon. While firing, it hits the first Clipping entity along its length, dealing damage every
+/// tick, and builds up heat. It locks out when it overheats, and can't fire again until it has fully cooled down.
+#[derive(Component)]
+pub struct BeamWeapon {
+    pub name: String,
+    // Damage per second dealt to whatever the beam is touching.
+    pub dps: f32,
+    pub dmg_type: DamageType,
+    pub range: f32,
+    pub width: f32,
+    pub sprite_path: String,
+    pub heat: HeatSink,
+    // Heat gained per second while firing. The beam only cools while it is off.
+    pub heat_rate: f32,
+    // Set by whatever controls the ship (player input or AI) every frame it wants the beam on.
+    pub firing: bool,
+    // Damage is dealt in ticks rather than every frame.
+    pub tick_timer: Timer,
+    pub sweep: Option<BeamSweep>,
+    pub sweep_phase: f32,
+    // Where the beam currently starts, which way it points and how long it is. Updated while firing.
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub length: f32,
+}
+
+impl BeamWeapon {
+    /// Whether the beam is actually on this frame.
+    pub fn active(&self) -> bool {
+        self.firing && !self.heat.overheated
+    }
+}
+
+/// Makes a beam sweep back and forth across the ship's nose.
+#[derive(Clone, Copy)]
+pub struct BeamSweep {
+    // How far to either side the beam sweeps, in radians.
+    pub amplitude: f32,
+    // How fast the beam sweeps, in radians of phase per second.
+    pub speed: f32,
+}
+
+/// The stretched sprite drawing a ship's beam.
+#[derive(Component)]
+pub struct BeamVisual {
+    pub owner: Entity,
+}
+
+/// Marks a ship whose beam already has a BeamVisual.
+#[derive(Component)]
+pub struct HasBeamVisual {}
+
+/// Debug text following an AI ship, showing which state its StateMachine is in.
+#[derive(Component)]
+pub struct AiStateLabel {
+    pub owner: Entity,
+}
+
+/// Marks a ship whose StateMachine already has an AiStateLabel.
+#[derive(Component)]
+pub struct HasAiStateLabel {}
+
 #[derive(Component)]
 pub struct Thruster {
     pub name: String,
@@ -108,6 +520,20 @@ pub struct Player {}
 #[derive(Component)]
 pub struct Enemy {}
 
+/// Something a destroyed enemy left behind, which the player collects by flying over it.
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    // Restores some of the player's health.
+    Repair,
+    // Refills the player's countermeasure charges.
+    Countermeasures,
+}
+
 #[derive(Component)]
 pub struct Asteroid {}
 
@@ -118,6 +544,125 @@ pub struct Star {}
 pub struct Missile {
     pub turn_speed: f32,
     pub fuel: f32,
+    pub seeker: Seeker,
+    // The blast when the missile detonates.
+    pub explosion: Explosion,
+}
+
+/// Marks large, solid entities, such as asteroids and the boss, that shield whatever is behind them from blockable
+/// explosions.
+#[derive(Component)]
+pub struct Cover {}
+
+/// A mine. It can't go off until its arming timer runs out, after which it detonates when a hostile ship comes
+/// within its trigger radius. What happens when it goes off depends on its kind.
+#[derive(Component, Clone)]
+pub struct Mine {
+    pub kind: MineKind,
+    pub arming_timer: Timer,
+    // Mines are set off by ships hostile to whoever laid them.
+    pub trigger_radius: f32,
+    pub explosion: Explosion,
+    // Armed mines blink each time this finishes.
+    pub blink_timer: Timer,
+}
+
+/// A ship whose thrusters have been knocked out by an EMP. The thruster's force is restored when the timer runs out.
+#[derive(Component)]
+pub struct EmpDisabled {
+    pub timer: Timer,
+    pub thruster_force: f32,
+}
+
+/// Left behind by a gravity mine. Pulls nearby ships toward its center, then collapses into an explosion.
+#[derive(Component)]
+pub struct GravityWell {
+    pub radius: f32,
+    // Momentum per second imparted on a ship at the center of the well.
+    pub strength: f32,
+    pub timer: Timer,
+    pub explosion: Explosion,
+}
+
+/// A decoy launched as a countermeasure. Missiles that come near it may be fooled into chasing it instead.
+#[derive(Component)]
+pub struct Decoy {
+    pub kind: DecoyKind,
+    pub radius: f32,
+    // Missiles that have already had their chance to be fooled by this decoy.
+    pub checked: Vec<Entity>,
+}
+
+/// A ship's countermeasure slot, which launches decoys from a limited number of charges.
+#[derive(Component)]
+pub struct CountermeasureLauncher {
+    pub kind: DecoyKind,
+    pub charges: u32,
+    pub max_charges: u32,
+    pub cd_timer: Timer,
+}
+
+/// Makes a homing projectile track a specific entity.
+#[derive(Component)]
+pub struct HomingTarget(pub Entity);
+
+/// The entity an AI-controlled ship or guided missile is going after. Ships with a TargetPolicy have theirs chosen
+/// for them every frame. Guided missiles are given theirs when launched.
+#[derive(Component)]
+pub struct AiTarget(pub Entity);
+
+/// A new target an enemy has noticed but not yet reacted to. Once the timer runs out, it becomes the AiTarget.
+#[derive(Component)]
+pub struct PendingTarget {
+    pub target: Entity,
+    pub timer: Timer,
+}
+
+/// The side a ship fights on. Whatever a ship fires, launches or lays fights on its side too. Things without a
+/// faction, like asteroids, are on nobody's side: anything can hurt them, and they can hurt anything.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Faction {
+    Player,
+    Pirates,
+    Aliens,
+}
+
+impl Faction {
+    pub fn from_name(name: &str) -> Option<Faction> {
+        match name {
+            "player" => Some(Faction::Player),
+            "pirates" => Some(Faction::Pirates),
+            "aliens" => Some(Faction::Aliens),
+            _ => None,
+        }
+    }
+}
+
+/// How an AI-controlled ship picks its AiTarget from the hostile ships around it.
+#[derive(Component, Clone, Copy)]
+pub enum TargetPolicy {
+    Nearest,
+    LowestHealth,
+    // Favors ships that are close and pointed at us.
+    MostThreatening,
+}
+
+/// Lets a ship lock onto a target by holding the lock key while aiming near it.
+#[derive(Component)]
+pub struct TargetLock {
+    // The entity currently locked on, if any.
+    pub target: Option<Entity>,
+    // The entity being locked onto. It becomes the target once lock_timer finishes.
+    pub candidate: Option<Entity>,
+    pub lock_timer: Timer,
+    // How far from the aim point a target can be and still be locked onto, in pixels.
+    pub range: f32,
+}
+
+/// Launches guided missiles at a ship's locked target.
+#[derive(Component)]
+pub struct MissileLauncher {
+    pub cd_timer: Timer,
 }
 
 #[derive(Component)]
@@ -125,6 +670,13 @@ pub struct Health {
     pub value: f32,
 }
 
+/// How well an AI ship leads its shots. 0.0 aims straight at the target, and 1.0 aims exactly where a target that
+/// holds its course will be when the shot arrives.
+#[derive(Component)]
+pub struct Accuracy {
+    pub value: f32,
+}
+
 #[derive(Component)]
 pub struct Mass {
     pub value: f32,
@@ -157,11 +709,6 @@ pub struct AITimer {
     pub cd_timer: Timer,
 }
 
-#[derive(Component)]
-pub struct AITimer2 {
-    pub cd_timer: Timer,
-}
-
 /// Entities with the Phase component can ONLY collide with NoPhase entities, NOT with other Phase entities.
 #[derive(Component)]
 pub struct Phase {}
@@ -184,6 +731,64 @@ pub struct Drag {
 #[derive(Component)]
 pub struct MainCamera {}
 
+/// Marks the HUD text showing the player's countermeasure charges.
+#[derive(Component)]
+pub struct CountermeasureHud {}
+
+/// Marks the HUD text showing the score.
+#[derive(Component)]
+pub struct ScoreHud {}
+
+/// Marks the debug text showing the adaptive difficulty modifier.
+#[derive(Component)]
+pub struct AdaptiveOverlay {}
+
+/// Marks the main menu's text, which is despawned when a game starts.
+#[derive(Component)]
+pub struct MainMenuText {}
+
+/// Makes a camera smoothly follow the player, leading ahead in the player's direction of travel.
+#[derive(Component)]
+pub struct FollowCamera {
+    // How quickly the camera catches up to its target. Higher is snappier.
+    pub smoothing: f32,
+    // How many frames of the player's movement the camera looks ahead by.
+    pub lead: f32,
+    // The furthest the camera will lead ahead of the player, in pixels.
+    pub max_lead: f32,
+    // The smoothed point the camera is looking at, before any camera shake is applied.
+    pub focus: Vec2,
+}
+
+/// Shakes a camera based on its current trauma. Trauma is added by CameraImpulseEvent events and decays over time.
+/// The amount of shake scales with the square of the trauma, so small impulses are subtle and big ones are violent.
+#[derive(Component)]
+pub struct CameraShake {
+    // Current trauma, from 0.0 (still) to 1.0 (maximum shake).
+    pub trauma: f32,
+    // How much trauma is lost per second.
+    pub decay: f32,
+    // The furthest the camera can be pushed from its focus, in pixels.
+    pub max_offset: f32,
+    // The furthest the camera can be rolled, in radians.
+    pub max_roll: f32,
+    // The offset applied this frame, which the follow camera adds on top of its focus.
+    pub offset: Vec2,
+}
+
+/// A floating number that drifts upward from a damaged entity and fades out.
+#[derive(Component)]
+pub struct DamageNumber {
+    pub velocity: Vec3,
+    pub timer: Timer,
+}
+
+/// Tints an entity's sprite for a brief moment after it takes damage.
+#[derive(Component)]
+pub struct HitFlash {
+    pub timer: Timer,
+}
+
 #[derive(Component, PartialEq)]
 pub enum EntityType {
     Player,
@@ -222,6 +827,481 @@ pub struct TurretAI {}
 #[derive(Component)]
 pub struct BossAI {}
 
+// ------------
+// -- Bosses --
+// ------------
+/// Something a boss can do during one of its phases.
+#[derive(Clone, Copy, PartialEq)]
+pub enum BossAttack {
+    // Every surviving missile bay launches a guided missile at the boss's target.
+    Missiles,
+    // A rammer arrives from the far side of the arena.
+    Rammers,
+    // A wave of drones launches from around the boss.
+    Drones,
+    // The spray laser fires whenever it is ready. Its own cooldown paces it.
+    Spray,
+    // The beam sweeps for as long as the boss has a target.
+    Beam,
+}
+
+/// An attack, and how often a boss uses it.
+pub struct BossPattern {
+    pub attack: BossAttack,
+    pub cooldown: Timer,
+}
+
+impl BossPattern {
+    pub fn every(attack: BossAttack, seconds: f32) -> BossPattern {
+        BossPattern {
+            attack,
+            cooldown: Timer::from_seconds(seconds, TimerMode::Repeating),
+        }
+    }
+
+    /// For attacks that aren't paced by the pattern, like the spray and the beam.
+    pub fn continuous(attack: BossAttack) -> BossPattern {
+        BossPattern::every(attack, 0.0)
+    }
+}
+
+/// One phase of a boss fight.
+pub struct BossPhase {
+    pub name: &'static str,
+    // The phase starts once the boss's health is at or below this fraction of its maximum, and its core is exposed.
+    pub health_threshold: f32,
+    pub patterns: Vec<BossPattern>,
+    // How fast the boss closes in on its target, in the same units as Velocity. 0.0 holds it in place.
+    pub drift_speed: f32,
+    // A track to loop from the start of the phase. None keeps the previous phase's track playing.
+    pub music: Option<&'static str>,
+}
+
+impl BossPhase {
+    pub fn uses(&self, attack: BossAttack) -> bool {
+        self.patterns.iter().any(|pattern| pattern.attack == attack)
+    }
+}
+
+/// Runs a boss through its phases, from the first at full health to the last. The boss's core takes no damage while
+/// any of its parts survive.
+#[derive(Component)]
+pub struct BossPhases {
+    pub phases: Vec<BossPhase>,
+    pub current: usize,
+    pub max_health: f32,
+    pub parts: Vec<Entity>,
+    // Where the boss holds position while it isn't drifting. Recorded the first time the boss is run.
+    pub anchor: Option<Vec3>,
+}
+
+impl BossPhases {
+    pub fn new(phases: Vec<BossPhase>, max_health: f32, parts: Vec<Entity>) -> BossPhases {
+        BossPhases {
+            phases,
+            current: 0,
+            max_health,
+            parts,
+            anchor: None,
+        }
+    }
+
+    pub fn phase(&self) -> &BossPhase {
+        &self.phases[self.current]
+    }
+
+    pub fn shielded(&self) -> bool {
+        !self.parts.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BossPartKind {
+    Turret,
+    MissileBay,
+}
+
+/// Marks the track playing for the current boss phase.
+#[derive(Component)]
+pub struct BossMusic {}
+
+/// A destructible piece of a boss, held at a fixed offset from it. Parts are ships of their own rather than children
+/// in the transform hierarchy, since everything else works in world coordinates.
+#[derive(Component)]
+pub struct BossPart {
+    pub boss: Entity,
+    pub kind: BossPartKind,
+    pub offset: Vec2,
+}
+
+// ------------
+// -- Squads --
+// ------------
+/// A group of enemy ships that fight together. The squad is its own entity, with no sprite, that keeps track of who
+/// is in it and who is in charge.
+#[derive(Component)]
+pub struct Squad {
+    pub name: String,
+    pub leader: Entity,
+    pub members: Vec<Entity>,
+}
+
+/// A ship flying in a Squad.
+#[derive(Component)]
+pub struct SquadMember {
+    pub squad: Entity,
+    /// Where the ship flies relative to its leader while the squad travels, turned to match the leader's heading.
+    pub offset: Vec2,
+    /// The angle, in radians, around the target that the ship attacks from, measured from the leader's line of attack.
+    pub flank_angle: f32,
+    /// Where the squad wants this ship to be. None leaves the ship to close in on its own.
+    pub goal: Option<Vec3>,
+    /// Whether the ship has reached its flanking position since the squad last closed in.
+    pub flanked: bool,
+}
+
+// ------------------------
+// -- AI State Machines --
+// ------------------------
+/// Run when an AI state is entered or exited, with the owning ship's transform and velocity.
+pub type StateHook = fn(&mut Transform, &mut Velocity);
+
+/// A single named state of a StateMachine.
+#[derive(Clone)]
+pub struct AiState {
+    pub name: &'static str,
+    /// How long the state's timer runs for. States without one never report TimerFinished.
+    pub duration: Option<f32>,
+    /// Whether coming back to the state picks its timer up where it left off, rather than restarting it. A timer that
+    /// has already finished restarts either way.
+    pub resume_timer: bool,
+    pub on_enter: Option<StateHook>,
+    pub on_exit: Option<StateHook>,
+}
+
+impl AiState {
+    pub fn new(name: &'static str) -> Self {
+        AiState {
+            name,
+            duration: None,
+            resume_timer: false,
+            on_enter: None,
+            on_exit: None,
+        }
+    }
+
+    pub fn timed(name: &'static str, seconds: f32) -> Self {
+        AiState {
+            duration: Some(seconds),
+            ..AiState::new(name)
+        }
+    }
+}
+
+/// What an AI can currently perceive about its target. Transition conditions are checked against it.
+#[derive(Clone, Copy)]
+pub struct Senses {
+    pub distance: f32,
+    /// The angle, in radians, the ship would need to turn to face its target.
+    pub angle: f32,
+}
+
+#[derive(Clone)]
+pub enum StateCondition {
+    Always,
+    TimerFinished,
+    TargetFartherThan(f32),
+    TargetCloserThan(f32),
+    /// The target is within this many radians of the ship's nose.
+    TargetAhead(f32),
+}
+
+impl StateCondition {
+    fn met(&self, timer: &Timer, senses: &Senses) -> bool {
+        match self {
+            StateCondition::Always => true,
+            StateCondition::TimerFinished => timer.finished(),
+            StateCondition::TargetFartherThan(range) => senses.distance > *range,
+            StateCondition::TargetCloserThan(range) => senses.distance <= *range,
+            StateCondition::TargetAhead(angle) => senses.angle.abs() < *angle,
+        }
+    }
+}
+
+/// Moves a StateMachine to another state when its condition is met.
+#[derive(Clone)]
+pub struct Transition {
+    /// The states this transition may leave. An empty list means any state.
+    pub from: Vec<&'static str>,
+    pub to: &'static str,
+    pub when: StateCondition,
+}
+
+impl Transition {
+    pub fn new(from: &[&'static str], to: &'static str, when: StateCondition) -> Self {
+        Transition {
+            from: from.to_vec(),
+            to,
+            when,
+        }
+    }
+}
+
+/// A data-driven finite state machine for enemy AI. Each state has its own timer, which only runs while the machine
+/// is in that state and restarts whenever the state is entered, unless the state resumes it. Transitions are checked in order and the first one whose condition holds is taken.
+/// The AI systems only decide what to do while in each state; when to change state lives here.
+#[derive(Component, Clone)]
+pub struct StateMachine {
+    pub states: Vec<AiState>,
+    pub transitions: Vec<Transition>,
+    current: usize,
+    previous: Option<usize>,
+    timers: Vec<Timer>,
+}
+
+impl StateMachine {
+    /// Creates a machine that starts in the first of its states. Panics if there are no states.
+    pub fn new(states: Vec<AiState>, transitions: Vec<Transition>) -> Self {
+        assert!(
+            !states.is_empty(),
+            "A StateMachine needs at least one state to start in"
+        );
+        let timers = states.iter().map(Self::state_timer).collect();
+        StateMachine {
+            states,
+            transitions,
+            current: 0,
+            previous: None,
+            timers,
+        }
+    }
+
+    fn state_timer(state: &AiState) -> Timer {
+        // Untimed states get a timer that never runs out. f32::MAX seconds would overflow a Duration.
+        let duration = state
+            .duration
+            .map_or(Duration::MAX, Duration::from_secs_f32);
+        Timer::new(duration, TimerMode::Once)
+    }
+
+    pub fn state(&self) -> &'static str {
+        self.states[self.current].name
+    }
+
+    pub fn previous_state(&self) -> Option<&'static str> {
+        self.previous.map(|i| self.states[i].name)
+    }
+
+    /// Seconds on the current state's timer. For states that resume their timer, this counts earlier visits too.
+    pub fn time_in_state(&self) -> f32 {
+        self.timers[self.current].elapsed_secs()
+    }
+
+    /// Forces the machine into a state, running the exit and enter hooks. Unknown states are ignored.
+    pub fn enter(
+        &mut self,
+        name: &'static str,
+        transform: &mut Transform,
+        velocity: &mut Velocity,
+    ) {
+        let Some(next) = self.states.iter().position(|s| s.name == name) else {
+            return;
+        };
+        if let Some(on_exit) = self.states[self.current].on_exit {
+            on_exit(transform, velocity);
+        }
+        self.previous = Some(self.current);
+        self.current = next;
+        if !self.states[next].resume_timer || self.timers[next].finished() {
+            self.timers[next] = Self::state_timer(&self.states[next]);
+        }
+        if let Some(on_enter) = self.states[next].on_enter {
+            on_enter(transform, velocity);
+        }
+    }
+
+    /// Ticks the current state's timer and takes the first transition that applies. Returns the resulting state.
+    pub fn update(
+        &mut self,
+        delta: Duration,
+        senses: &Senses,
+        transform: &mut Transform,
+        velocity: &mut Velocity,
+    ) -> &'static str {
+        self.timers[self.current].tick(delta);
+        let timer = &self.timers[self.current];
+        let current = self.state();
+        let next = self
+            .transitions
+            .iter()
+            .filter(|t| t.to != current && (t.from.is_empty() || t.from.contains(&current)))
+            .find(|t| t.when.met(timer, senses))
+            .map(|t| t.to);
+        if let Some(next) = next {
+            self.enter(next, transform, velocity);
+        }
+        self.state()
+    }
+}
+
+// --------------------
+// -- Behavior Trees --
+// --------------------
+#[derive(Clone, Copy, PartialEq)]
+pub enum BtStatus {
+    Success,
+    Failure,
+    Running,
+}
+
+/// A node of a BehaviorTree. Composites refer to their children by index into the tree's nodes.
+#[derive(Clone)]
+pub enum BtNode {
+    /// Ticks children in order until one does not fail.
+    Selector(Vec<usize>),
+    /// Ticks children in order until one does not succeed. A running child is resumed on the next tick.
+    Sequence(Vec<usize>),
+    TargetCloserThan(f32),
+    TargetFartherThan(f32),
+    /// The target is within this many radians of the ship's nose.
+    TargetAhead(f32),
+    TurnToward,
+    /// Fires the thruster at a fraction of its force.
+    Thrust(f32),
+    /// Fires weapon 1, 2 or 3 (primary, secondary or tertiary). Fails if the weapon isn't ready.
+    Fire(usize),
+    /// Thrusts forward or backward to hold roughly this distance from the target.
+    KeepDistance(f32),
+    /// Circles the target at this distance.
+    Orbit(f32),
+    Flee,
+    /// Launches a guided missile at the target from the ship's MissileLauncher. Fails if the ship has none, or it is
+    /// still reloading.
+    LaunchMissile,
+    /// Runs for this many seconds, then succeeds.
+    Wait(f32),
+}
+
+impl BtNode {
+    fn parse(line: &str) -> Result<BtNode, String> {
+        let fields = line.split(',').map(str::trim).collect::<Vec<&str>>();
+        let arg = |i: usize| -> Result<f32, String> {
+            fields
+                .get(i)
+                .ok_or(format!("'{}' is missing an argument", fields[0]))?
+                .parse::<f32>()
+                .map_err(|e| format!("'{}': {}", line, e))
+        };
+        Ok(match fields[0] {
+            "selector" => BtNode::Selector(Vec::new()),
+            "sequence" => BtNode::Sequence(Vec::new()),
+            "closer_than" => BtNode::TargetCloserThan(arg(1)?),
+            "farther_than" => BtNode::TargetFartherThan(arg(1)?),
+            "facing" => BtNode::TargetAhead(arg(1)?),
+            "turn_toward" => BtNode::TurnToward,
+            "thrust" => BtNode::Thrust(if fields.len() > 1 { arg(1)? } else { 1.0 }),
+            "fire" => BtNode::Fire(arg(1)? as usize),
+            "keep_distance" => BtNode::KeepDistance(arg(1)?),
+            "orbit" => BtNode::Orbit(arg(1)?),
+            "flee" => BtNode::Flee,
+            "launch_missile" => BtNode::LaunchMissile,
+            "wait" => BtNode::Wait(arg(1)?),
+            other => return Err(format!("unknown node '{}'", other)),
+        })
+    }
+
+    fn is_composite(&self) -> bool {
+        matches!(self, BtNode::Selector(_) | BtNode::Sequence(_))
+    }
+
+    pub fn children(&self) -> &[usize] {
+        match self {
+            BtNode::Selector(children) | BtNode::Sequence(children) => children,
+            _ => &[],
+        }
+    }
+}
+
+/// An enemy behavior loaded from a text file in assets/behaviors/. Each line is one node, and children are indented
+/// two spaces deeper than their parent. Arguments follow the node name, separated by commas, as in the level script.
+/// Lines starting with '#' are comments.
+#[derive(Component, Clone)]
+pub struct BehaviorTree {
+    pub name: String,
+    /// The root is always the first node.
+    pub nodes: Vec<BtNode>,
+    /// Which child each running sequence resumes from.
+    pub cursor: Vec<usize>,
+    /// How long each wait node has been running.
+    pub elapsed: Vec<f32>,
+}
+
+impl BehaviorTree {
+    pub fn parse(name: &str, txt: &str) -> Result<BehaviorTree, String> {
+        let mut nodes: Vec<BtNode> = Vec::new();
+        // The chain of composites above the current line, with their depths.
+        let mut parents: Vec<(usize, usize)> = Vec::new();
+        for (line_num, line) in txt.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let err = |msg: String| format!("{}.txt line {}: {}", name, line_num + 1, msg);
+            let depth = (line.len() - line.trim_start().len()) / 2;
+            let node = BtNode::parse(trimmed).map_err(err)?;
+            let index = nodes.len();
+
+            while parents.last().is_some_and(|(d, _)| *d >= depth) {
+                parents.pop();
+            }
+            // Children sit exactly one level deeper than their parent.
+            match parents.last() {
+                Some((parent_depth, _)) if depth != parent_depth + 1 => {
+                    return Err(err("unexpected indentation".to_string()))
+                }
+                Some((_, parent)) => match &mut nodes[*parent] {
+                    BtNode::Selector(children) | BtNode::Sequence(children) => children.push(index),
+                    _ => unreachable!(),
+                },
+                None if index > 0 => return Err(err("a tree can only have one root".to_string())),
+                None if depth > 0 => return Err(err("unexpected indentation".to_string())),
+                None => {}
+            }
+            if node.is_composite() {
+                parents.push((depth, index));
+            }
+            nodes.push(node);
+        }
+
+        if nodes.is_empty() {
+            return Err(format!("{}.txt has no nodes", name));
+        }
+        if nodes
+            .iter()
+            .any(|n| n.is_composite() && n.children().is_empty())
+        {
+            return Err(format!(
+                "{}.txt has a selector or sequence with no children",
+                name
+            ));
+        }
+        Ok(BehaviorTree {
+            name: name.to_string(),
+            cursor: vec![0; nodes.len()],
+            elapsed: vec![0.0; nodes.len()],
+            nodes,
+        })
+    }
+
+    /// Forgets any progress made by the node and everything below it.
+    pub fn reset(&mut self, index: usize) {
+        self.cursor[index] = 0;
+        self.elapsed[index] = 0.0;
+        for child in self.nodes[index].children().to_vec() {
+            self.reset(child);
+        }
+    }
+}
 // ---------------
 // -- Resources --
 // ---------------
@@ -230,12 +1310,350 @@ pub struct WorldCoords {
     pub coords: Vec2,
 }
 
+/// The size of the playable world in pixels, from the origin to (width, height), read from assets/arena.txt.
+/// Entities wrap around its edges.
+/// This is independent of the window size; the camera follows the player around the arena.
+#[derive(Resource)]
+pub struct Arena {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Arena {
+    pub fn load(path: &str) -> Arena {
+        let txt = std::fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Expected to find the arena size at {}", path));
+        Arena::parse(&txt).unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    /// Reads a single "width,height" line. Blank lines and lines starting with '#' are skipped.
+    pub fn parse(txt: &str) -> Result<Arena, String> {
+        let mut lines = txt
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let line = lines.next().ok_or("no arena size given")?;
+        if lines.next().is_some() {
+            return Err("expected a single width,height line".to_string());
+        }
+        let size = line
+            .split(',')
+            .map(|f| f.trim().parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|e| format!("'{}': {}", line, e))?;
+        match size[..] {
+            [width, height] if width > 0.0 && height > 0.0 => Ok(Arena { width, height }),
+            _ => Err(format!("bad arena size '{}'", line)),
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+}
+
+/// Player-facing options. These can be flipped at runtime with the hotkeys in settings_sys.rs.
+#[derive(Resource)]
+pub struct Settings {
+    pub damage_numbers: bool,
+    pub hit_flash: bool,
+    pub minimap: bool,
+    pub edge_indicators: bool,
+    // Accessibility: scales all camera shake, from 0.0 (off) to 1.0 (full).
+    pub screen_shake_intensity: f32,
+    // Accessibility: briefly freezes the game on big impacts.
+    pub hit_stop: bool,
+    // Debugging: labels each AI ship with its current state.
+    pub ai_states: bool,
+    // Debugging: shows the adaptive difficulty modifier.
+    pub adaptive_overlay: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            damage_numbers: true,
+            hit_flash: true,
+            minimap: true,
+            edge_indicators: true,
+            screen_shake_intensity: 1.0,
+            hit_stop: true,
+            ai_states: false,
+            adaptive_overlay: false,
+        }
+    }
+}
+
+/// Tracks an active hit-stop. While the timer runs, virtual time is paused. The timer is ticked in real time.
+#[derive(Resource, Default)]
+pub struct HitStop {
+    pub timer: Option<Timer>,
+}
+
+/// Where every ship that can be targeted was at the start of the frame. AI systems read their target's position
+/// from here, so they don't need to query the transforms of other ships while moving their own.
+#[derive(Resource, Default)]
+pub struct TargetCache {
+    pub targets: HashMap<Entity, TargetInfo>,
+}
+
+impl TargetCache {
+    pub fn get(&self, entity: Entity) -> Option<&TargetInfo> {
+        self.targets.get(&entity)
+    }
+}
+
+/// A snapshot of a targetable ship.
+pub struct TargetInfo {
+    pub transform: Transform,
+    pub velocity: Vec3,
+}
+
+// Side length, in pixels, of the cells of the ObstacleGrid.
+pub const OBSTACLE_CELL_SIZE: f32 = 200.0;
+
+/// Where every solid object was at the start of the frame, bucketed into a coarse grid so that AI ships can cheaply
+/// find what is around them without looping over everything in the arena.
+#[derive(Resource, Default)]
+pub struct ObstacleGrid {
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<Obstacle>>,
+}
+
+/// A snapshot of something AI ships should steer around.
+#[derive(Clone)]
+pub struct Obstacle {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+impl ObstacleGrid {
+    /// Empties the grid, resizing it to cover the arena.
+    pub fn clear(&mut self, arena: &Arena) {
+        self.columns = (arena.width / OBSTACLE_CELL_SIZE).ceil().max(1.0) as usize;
+        self.rows = (arena.height / OBSTACLE_CELL_SIZE).ceil().max(1.0) as usize;
+        self.cells.clear();
+        self.cells.resize(self.columns * self.rows, Vec::new());
+    }
+
+    fn cell(&self, column: i32, row: i32) -> usize {
+        let column = column.rem_euclid(self.columns as i32) as usize;
+        let row = row.rem_euclid(self.rows as i32) as usize;
+        row * self.columns + column
+    }
+
+    fn cell_coords(position: Vec3) -> (i32, i32) {
+        (
+            (position.x / OBSTACLE_CELL_SIZE).floor() as i32,
+            (position.y / OBSTACLE_CELL_SIZE).floor() as i32,
+        )
+    }
+
+    pub fn insert(&mut self, obstacle: Obstacle) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let (column, row) = Self::cell_coords(obstacle.position);
+        let cell = self.cell(column, row);
+        self.cells[cell].push(obstacle);
+    }
+
+    /// Every obstacle in the cells within `radius` of `position`, wrapping around the arena's edges. This may include
+    /// obstacles slightly farther away than `radius`, so callers should still check the distance.
+    pub fn nearby(&self, position: Vec3, radius: f32) -> Vec<&Obstacle> {
+        if self.cells.is_empty() {
+            return Vec::new();
+        }
+        let (column, row) = Self::cell_coords(position);
+        let reach = (radius / OBSTACLE_CELL_SIZE).ceil() as i32;
+        let mut cells = Vec::new();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                cells.push(self.cell(column + dx, row + dy));
+            }
+        }
+        // A small arena can wrap the search area back onto itself.
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+            .iter()
+            .flat_map(|&cell| self.cells[cell].iter())
+            .collect()
+    }
+}
+
 #[derive(Resource)]
 pub struct ExplosionSound {
     sound: Handle<AudioSource>,
 }
 // Structs and Enums that are NOT Components
 
+/// One ship of a squad template.
+#[derive(Clone)]
+pub struct SquadSlot {
+    pub ship_type: String,
+    pub offset: Vec2,
+    pub flank_angle: f32,
+}
+
+/// How one faction treats another.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stance {
+    // They can't hurt each other, and don't set off each other's missiles or mines.
+    Allied,
+    // They leave each other alone, but stray shots and blasts still hurt.
+    Neutral,
+    // They go after each other.
+    Hostile,
+}
+
+/// How the factions treat each other, read from a file of "faction,faction,stance" lines. Stances go both ways.
+/// Factions are allied with themselves and hostile to everyone else, unless the file says otherwise.
+#[derive(Resource, Default)]
+pub struct FactionTable {
+    pub stances: HashMap<(Faction, Faction), Stance>,
+}
+
+impl FactionTable {
+    pub fn load(path: &str) -> FactionTable {
+        let txt = std::fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Expected to find the faction table at {}", path));
+        FactionTable::parse(&txt).unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    pub fn parse(txt: &str) -> Result<FactionTable, String> {
+        let mut table = FactionTable::default();
+        for line in txt
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            let fields = line.split(',').map(str::trim).collect::<Vec<&str>>();
+            let (Some(a), Some(b), Some(stance)) = (
+                fields.first().and_then(|f| Faction::from_name(f)),
+                fields.get(1).and_then(|f| Faction::from_name(f)),
+                fields.get(2).and_then(|f| match *f {
+                    "allied" => Some(Stance::Allied),
+                    "neutral" => Some(Stance::Neutral),
+                    "hostile" => Some(Stance::Hostile),
+                    _ => None,
+                }),
+            ) else {
+                return Err(format!("bad faction line '{}'", line));
+            };
+            table.stances.insert((a, b), stance);
+            table.stances.insert((b, a), stance);
+        }
+        Ok(table)
+    }
+
+    pub fn stance(&self, a: Faction, b: Faction) -> Stance {
+        match self.stances.get(&(a, b)) {
+            Some(stance) => *stance,
+            None if a == b => Stance::Allied,
+            None => Stance::Hostile,
+        }
+    }
+
+    /// Whether ships on side `a` should go after things on side `b`. Nobody goes after things without a side.
+    pub fn hostile(&self, a: Option<Faction>, b: Option<Faction>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => self.stance(a, b) == Stance::Hostile,
+            _ => false,
+        }
+    }
+
+    /// Whether something on side `a` can damage, or set off ordnance against, something on side `b`.
+    pub fn can_harm(&self, a: Option<Faction>, b: Option<Faction>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => self.stance(a, b) != Stance::Allied,
+            _ => true,
+        }
+    }
+}
+
+/// Every squad template found in assets/squads/, keyed by file name without the extension. Each line of a template
+/// is "ship_type,offset_x,offset_y,flank_angle", and the first line is the leader.
+#[derive(Resource, Default)]
+pub struct SquadLibrary {
+    pub squads: HashMap<String, Vec<SquadSlot>>,
+}
+
+impl SquadLibrary {
+    pub fn load(dir: &str) -> SquadLibrary {
+        let mut library = SquadLibrary::default();
+        let entries =
+            std::fs::read_dir(dir).unwrap_or_else(|_| panic!("Expected to find squads in {}", dir));
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "txt") {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                let txt = std::fs::read_to_string(&path).unwrap();
+                let slots = txt
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| {
+                        let fields = line.split(',').map(str::trim).collect::<Vec<&str>>();
+                        let number = |i: usize| {
+                            fields
+                                .get(i)
+                                .and_then(|f| f.parse::<f32>().ok())
+                                .unwrap_or_else(|| {
+                                    panic!("{}.txt: bad squad line '{}'", name, line)
+                                })
+                        };
+                        SquadSlot {
+                            ship_type: fields[0].to_string(),
+                            offset: Vec2::new(number(1), number(2)),
+                            flank_angle: number(3),
+                        }
+                    })
+                    .collect::<Vec<SquadSlot>>();
+                if slots.is_empty() {
+                    panic!("{}.txt has no ships in it", name);
+                }
+                library.squads.insert(name, slots);
+            }
+        }
+        library
+    }
+}
+
+/// Every behavior tree found in assets/behaviors/, keyed by file name without the extension.
+#[derive(Resource, Default)]
+pub struct BehaviorLibrary {
+    pub trees: HashMap<String, BehaviorTree>,
+}
+
+impl BehaviorLibrary {
+    pub fn load(dir: &str) -> BehaviorLibrary {
+        let mut library = BehaviorLibrary::default();
+        let entries = std::fs::read_dir(dir)
+            .unwrap_or_else(|_| panic!("Expected to find behavior trees in {}", dir));
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "txt") {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                let txt = std::fs::read_to_string(&path).unwrap();
+                let tree = BehaviorTree::parse(&name, &txt).unwrap_or_else(|e| panic!("{}", e));
+                library.trees.insert(name, tree);
+            }
+        }
+        library
+    }
+
+    /// A fresh copy of the named tree, ready to attach to a ship.
+    pub fn get(&self, name: &str) -> BehaviorTree {
+        self.trees
+            .get(name)
+            .unwrap_or_else(|| panic!("Expected to find {}.txt in assets/behaviors/", name))
+            .clone()
+    }
+}
+
 #[derive(Resource)]
 pub struct LevelScript {
     pub txt: String,
@@ -252,6 +1670,243 @@ pub struct ScriptTimer {
     pub delay: Timer,
 }
 
+/// Whether the player is picking a difficulty in the main menu or playing.
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    InGame,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DifficultyLevel {
+    Easy,
+    Normal,
+    Hard,
+    Custom,
+}
+
+impl DifficultyLevel {
+    pub const ALL: [DifficultyLevel; 4] = [
+        DifficultyLevel::Easy,
+        DifficultyLevel::Normal,
+        DifficultyLevel::Hard,
+        DifficultyLevel::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyLevel::Easy => "easy",
+            DifficultyLevel::Normal => "normal",
+            DifficultyLevel::Hard => "hard",
+            DifficultyLevel::Custom => "custom",
+        }
+    }
+}
+
+/// How hard the game is. Every value is a multiplier where 1.0 is the normal game, except the reaction delay, which is
+/// how many seconds an enemy takes to switch to a new target.
+#[derive(Resource, Clone, Copy)]
+pub struct Difficulty {
+    pub level: DifficultyLevel,
+    pub enemy_health: f32,
+    pub enemy_damage: f32,
+    pub reaction_delay: f32,
+    pub enemy_accuracy: f32,
+    pub script_delay: f32,
+    pub incoming_damage: f32,
+}
+
+impl Difficulty {
+    /// The settings for a level. Custom starts from the normal settings.
+    pub fn preset(level: DifficultyLevel) -> Difficulty {
+        let (
+            enemy_health,
+            enemy_damage,
+            reaction_delay,
+            enemy_accuracy,
+            script_delay,
+            incoming_damage,
+        ) = match level {
+            DifficultyLevel::Easy => (0.75, 0.6, 0.6, 0.5, 1.3, 0.6),
+            DifficultyLevel::Normal | DifficultyLevel::Custom => (1.0, 1.0, 0.25, 1.0, 1.0, 1.0),
+            DifficultyLevel::Hard => (1.4, 1.3, 0.05, 1.25, 0.8, 1.25),
+        };
+        Difficulty {
+            level,
+            enemy_health,
+            enemy_damage,
+            reaction_delay,
+            enemy_accuracy,
+            script_delay,
+            incoming_damage,
+        }
+    }
+
+    /// The values that can be tuned by hand on the custom difficulty, with their names.
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut f32); 6] {
+        [
+            ("Enemy health", &mut self.enemy_health),
+            ("Enemy damage", &mut self.enemy_damage),
+            ("Reaction delay", &mut self.reaction_delay),
+            ("Enemy accuracy", &mut self.enemy_accuracy),
+            ("Wave delay", &mut self.script_delay),
+            ("Damage taken", &mut self.incoming_damage),
+        ]
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::preset(DifficultyLevel::Normal)
+    }
+}
+
+/// Which row of the main menu is highlighted. The first rows are the difficulty levels, followed by the custom values.
+#[derive(Resource, Default)]
+pub struct MenuCursor {
+    pub row: usize,
+}
+
+/// Points earned in the current game.
+#[derive(Resource, Default)]
+pub struct Score {
+    pub points: u32,
+}
+
+pub const HIGH_SCORE_COUNT: usize = 10;
+
+pub struct HighScore {
+    pub points: u32,
+    pub difficulty: String,
+}
+
+/// The best scores so far, best first, each with the difficulty it was earned on.
+#[derive(Resource, Default)]
+pub struct HighScores {
+    pub path: String,
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Reads the table from a file of "points,difficulty" lines. A missing file is an empty table.
+    pub fn load(path: &str) -> HighScores {
+        let txt = std::fs::read_to_string(path).unwrap_or_default();
+        let mut entries = txt
+            .lines()
+            .filter_map(|line| {
+                let (points, difficulty) = line.trim().split_once(',')?;
+                Some(HighScore {
+                    points: points.parse().ok()?,
+                    difficulty: difficulty.to_string(),
+                })
+            })
+            .collect::<Vec<HighScore>>();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.points));
+        entries.truncate(HIGH_SCORE_COUNT);
+        HighScores {
+            path: path.to_string(),
+            entries,
+        }
+    }
+
+    pub fn save(&self) {
+        let txt = self
+            .entries
+            .iter()
+            .map(|entry| format!("{},{}\n", entry.points, entry.difficulty))
+            .collect::<String>();
+        if let Err(e) = std::fs::write(&self.path, txt) {
+            println!("Could not save high scores to {}: {}", self.path, e);
+        }
+    }
+
+    /// Adds a score to the table if it is good enough to make it.
+    pub fn record(&mut self, points: u32, difficulty: &str) {
+        let rank = self.entries.partition_point(|entry| entry.points >= points);
+        if rank < HIGH_SCORE_COUNT {
+            self.entries.insert(
+                rank,
+                HighScore {
+                    points,
+                    difficulty: difficulty.to_string(),
+                },
+            );
+            self.entries.truncate(HIGH_SCORE_COUNT);
+        }
+    }
+}
+
+// The bounds of the adaptive modifier. 1.0 leaves the game as the difficulty set it.
+pub const ADAPTIVE_MIN: f32 = 0.6;
+pub const ADAPTIVE_MAX: f32 = 1.4;
+// How often the player's performance is judged and the modifier nudged, in seconds.
+pub const ADAPTIVE_WINDOW: f32 = 10.0;
+// The player is close to death below this much health.
+pub const NEAR_DEATH_HEALTH: f32 = 0.2 * PLAYER_HEALTH;
+// The chance a destroyed enemy leaves a pickup behind when the modifier is 1.0.
+pub const BASE_DROP_CHANCE: f32 = 0.1;
+
+/// The optional adaptive mode, which watches how the player is doing and eases off or pushes harder on top of the
+/// difficulty. The modifier is below 1.0 when the player is struggling and above it when they are cruising.
+#[derive(Resource)]
+pub struct AdaptiveDifficulty {
+    pub enabled: bool,
+    pub modifier: f32,
+    // What happened since the modifier was last nudged.
+    pub damage_taken: f32,
+    pub kills: u32,
+    pub near_death: bool,
+    pub window: Timer,
+}
+
+impl Default for AdaptiveDifficulty {
+    fn default() -> Self {
+        AdaptiveDifficulty {
+            enabled: false,
+            modifier: 1.0,
+            damage_taken: 0.0,
+            kills: 0,
+            near_death: false,
+            window: Timer::from_seconds(ADAPTIVE_WINDOW, TimerMode::Repeating),
+        }
+    }
+}
+
+impl AdaptiveDifficulty {
+    /// Judges the last window and nudges the modifier, then starts a new window.
+    pub fn nudge(&mut self) {
+        let step = if self.near_death {
+            -0.15
+        } else if self.damage_taken > 150.0 {
+            -0.05
+        } else if self.damage_taken < 50.0 && self.kills >= 3 {
+            0.05
+        } else {
+            0.0
+        };
+        self.modifier = (self.modifier + step).clamp(ADAPTIVE_MIN, ADAPTIVE_MAX);
+        self.damage_taken = 0.0;
+        self.kills = 0;
+        self.near_death = false;
+    }
+
+    /// Scales the delays between waves. Struggling players get longer breaks.
+    pub fn spawn_delay(&self) -> f32 {
+        2.0 - self.modifier
+    }
+
+    /// Scales how well newly spawned enemies aim.
+    pub fn accuracy(&self) -> f32 {
+        self.modifier
+    }
+
+    /// The chance a destroyed enemy leaves a pickup behind. Struggling players get more of them.
+    pub fn drop_chance(&self) -> f32 {
+        BASE_DROP_CHANCE * (2.0 - self.modifier)
+    }
+}
+
 pub enum Shape {
     Circle,
     Square,
@@ -270,3 +1925,340 @@ pub enum ProjectileType {
     Torpedo,
     Shells,
 }
+
+/// How a guided missile finds its target, which decides how easily it is fooled by each kind of decoy.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Seeker {
+    Heat,
+    Radar,
+}
+
+impl Seeker {
+    /// The chance that a missile with this seeker switches to chasing a decoy of the given kind.
+    pub fn decoy_chance(&self, kind: DecoyKind) -> f32 {
+        match (self, kind) {
+            (Seeker::Heat, DecoyKind::Flare) => 0.6,
+            (Seeker::Heat, DecoyKind::Chaff) => 0.15,
+            (Seeker::Radar, DecoyKind::Flare) => 0.1,
+            (Seeker::Radar, DecoyKind::Chaff) => 0.6,
+        }
+    }
+}
+
+/// Flares fool heat-seeking missiles, chaff fools radar-guided missiles.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DecoyKind {
+    Flare,
+    Chaff,
+}
+
+impl DecoyKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DecoyKind::Flare => "Flares",
+            DecoyKind::Chaff => "Chaff",
+        }
+    }
+}
+
+/// What a mine does when it goes off. Standard mines just explode. EMP mines knock out the thrusters of ships caught
+/// in the blast. Cluster mines scatter smaller mines around them. Gravity mines open a gravity well that drags ships
+/// in before collapsing.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MineKind {
+    Standard,
+    Emp,
+    Cluster,
+    Gravity,
+}
+
+impl MineKind {
+    /// The color an armed mine of this kind blinks.
+    pub fn color(&self) -> Color {
+        match self {
+            MineKind::Standard => Color::RED,
+            MineKind::Emp => Color::CYAN,
+            MineKind::Cluster => Color::ORANGE,
+            MineKind::Gravity => Color::PURPLE,
+        }
+    }
+}
+
+/// The size and strength of an explosion.
+#[derive(Clone, Copy)]
+pub struct Explosion {
+    pub radius: f32,
+    pub damage: f32,
+    // The momentum imparted on something at the center of the blast.
+    pub force: f32,
+    // How damage and force weaken toward the edge of the blast.
+    pub falloff: Falloff,
+    // Whether entities with Cover shield anything behind them from the blast.
+    pub blockable: bool,
+}
+
+/// How an explosion's strength drops off between its center and its edge.
+#[derive(Clone, Copy)]
+pub enum Falloff {
+    // Full strength all the way to the edge.
+    Flat,
+    Linear,
+    // Stays strong near the center, then drops off quickly near the edge.
+    Quadratic,
+}
+
+impl Falloff {
+    /// The fraction of full strength felt at `t`, where 0 is the center of the blast and 1 is its edge.
+    pub fn scale(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Falloff::Flat => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::Quadratic => 1.0 - t * t,
+        }
+    }
+}
+
+/// Describes how a type of projectile behaves when it hits something, when it runs out of fuel, and in flight.
+pub struct ProjectileBehavior {
+    // Whether the projectile's kinetic energy is added to its damage on a direct hit.
+    pub kinetic: bool,
+    // If set, the projectile explodes on impact instead of dealing direct damage.
+    pub hit_explosion: Option<Explosion>,
+    // If set, the projectile explodes when it runs out of fuel instead of just disappearing.
+    pub expiry_explosion: Option<Explosion>,
+    // If set, the projectile steers toward targets in front of it at this turn speed, in radians per frame.
+    pub homing_turn_speed: Option<f32>,
+    // If set, this sprite is briefly shown at the point of a direct hit.
+    pub impact_sprite_path: Option<&'static str>,
+}
+
+impl ProjectileType {
+    pub fn behavior(&self) -> ProjectileBehavior {
+        match self {
+            // Lasers have no mass, their damage is based on their base damage value.
+            ProjectileType::Laser => ProjectileBehavior {
+                kinetic: false,
+                hit_explosion: None,
+                expiry_explosion: None,
+                homing_turn_speed: None,
+                impact_sprite_path: None,
+            },
+            // Shells do have mass, and their damage is their kinetic energy.
+            ProjectileType::Shells => ProjectileBehavior {
+                kinetic: true,
+                hit_explosion: None,
+                expiry_explosion: None,
+                homing_turn_speed: None,
+                impact_sprite_path: Some("sprites/effects/explosion_tmp.png"),
+            },
+            // Torpedoes are slow and heavy, and blow up whether they hit something or not.
+            ProjectileType::Torpedo => ProjectileBehavior {
+                kinetic: false,
+                hit_explosion: Some(Explosion {
+                    radius: 120.0,
+                    damage: 40.0,
+                    force: 800.0,
+                    falloff: Falloff::Linear,
+                    blockable: true,
+                }),
+                expiry_explosion: Some(Explosion {
+                    radius: 120.0,
+                    damage: 40.0,
+                    force: 800.0,
+                    falloff: Falloff::Linear,
+                    blockable: true,
+                }),
+                homing_turn_speed: None,
+                impact_sprite_path: None,
+            },
+            // Missiles home in on targets ahead of them and explode on impact, but fizzle out if they miss.
+            ProjectileType::Missile => ProjectileBehavior {
+                kinetic: false,
+                hit_explosion: Some(Explosion {
+                    radius: 80.0,
+                    damage: 25.0,
+                    force: 500.0,
+                    // The blast is small enough that it hits just as hard at its edge.
+                    falloff: Falloff::Flat,
+                    blockable: true,
+                }),
+                expiry_explosion: None,
+                homing_turn_speed: Some(0.06),
+                impact_sprite_path: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_behavior_trees() {
+        let txt =
+            "# comment\nselector\n  sequence\n    farther_than,800\n    launch_missile\n  fire,1\n";
+        let tree = BehaviorTree::parse("test", txt).unwrap();
+        assert_eq!(tree.nodes.len(), 5);
+        assert_eq!(tree.nodes[0].children(), &[1, 4]);
+        assert_eq!(tree.nodes[1].children(), &[2, 3]);
+        assert!(matches!(tree.nodes[2], BtNode::TargetFartherThan(d) if d == 800.0));
+        assert!(matches!(tree.nodes[4], BtNode::Fire(1)));
+    }
+
+    #[test]
+    fn rejects_children_indented_too_deep() {
+        let err = BehaviorTree::parse("test", "selector\n    fire,1\n")
+            .err()
+            .unwrap();
+        assert!(
+            err.contains("line 2") && err.contains("indentation"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn rejects_an_indented_root() {
+        let err = BehaviorTree::parse("test", "  selector\n    fire,1\n")
+            .err()
+            .unwrap();
+        assert!(err.contains("indentation"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_more_than_one_root() {
+        let err = BehaviorTree::parse("test", "fire,1\nflee\n").err().unwrap();
+        assert!(err.contains("one root"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_unknown_nodes_and_missing_arguments() {
+        let err = BehaviorTree::parse("test", "selector\n  dance\n")
+            .err()
+            .unwrap();
+        assert!(err.contains("unknown node 'dance'"), "{}", err);
+        let err = BehaviorTree::parse("test", "selector\n  keep_distance\n")
+            .err()
+            .unwrap();
+        assert!(err.contains("missing an argument"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_empty_trees_and_composites() {
+        assert!(BehaviorTree::parse("test", "# nothing here\n").is_err());
+        assert!(BehaviorTree::parse("test", "selector\n  sequence\n").is_err());
+    }
+
+    #[test]
+    fn faction_stances_go_both_ways() {
+        let table = FactionTable::parse("player,aliens,neutral\n").unwrap();
+        assert_eq!(
+            table.stance(Faction::Player, Faction::Aliens),
+            Stance::Neutral
+        );
+        assert_eq!(
+            table.stance(Faction::Aliens, Faction::Player),
+            Stance::Neutral
+        );
+    }
+
+    #[test]
+    fn unlisted_factions_are_allied_with_themselves_and_hostile_to_others() {
+        let table = FactionTable::parse("# nothing listed\n").unwrap();
+        assert_eq!(
+            table.stance(Faction::Pirates, Faction::Pirates),
+            Stance::Allied
+        );
+        assert_eq!(
+            table.stance(Faction::Pirates, Faction::Aliens),
+            Stance::Hostile
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_stances_and_factions() {
+        assert!(FactionTable::parse("player,pirates,friendly\n").is_err());
+        assert!(FactionTable::parse("player,robots,hostile\n").is_err());
+        assert!(FactionTable::parse("player,pirates\n").is_err());
+    }
+
+    #[test]
+    fn allies_cannot_harm_each_other_but_neutrals_can() {
+        let table = FactionTable::parse("pirates,aliens,neutral\n").unwrap();
+        let (pirates, aliens) = (Some(Faction::Pirates), Some(Faction::Aliens));
+        assert!(!table.can_harm(pirates, pirates));
+        assert!(table.can_harm(pirates, aliens));
+        assert!(!table.hostile(pirates, aliens));
+        assert!(table.hostile(pirates, Some(Faction::Player)));
+    }
+
+    #[test]
+    fn things_without_a_side_can_be_harmed_but_not_hunted() {
+        let table = FactionTable::default();
+        assert!(table.can_harm(None, Some(Faction::Player)));
+        assert!(table.can_harm(Some(Faction::Player), None));
+        assert!(!table.hostile(Some(Faction::Player), None));
+    }
+
+    #[test]
+    fn releasing_the_trigger_ends_a_burst() {
+        let mut weapon = crate::ship_parts::load_boss_spray_laser();
+        weapon.fire_pattern(&Transform::default(), 0.0);
+        weapon.tick(Duration::from_secs_f32(0.1));
+        assert!(weapon.ready(), "the next shot of the burst should be due");
+        weapon.release_trigger();
+        assert!(
+            !weapon.ready(),
+            "a dropped burst should wait out the cooldown"
+        );
+        weapon.tick(Duration::from_secs_f32(5.0));
+        assert!(weapon.ready());
+    }
+
+    #[test]
+    fn picket_resumes_its_launch_countdown_after_backing_off() {
+        let mut brain = crate::ship_parts::load_picket_ai();
+        let (mut transform, mut velocity) = (
+            Transform::default(),
+            Velocity {
+                velocity: Vec3::ZERO,
+            },
+        );
+        let far = Senses {
+            distance: 800.0,
+            angle: 0.0,
+        };
+        let close = Senses {
+            distance: 300.0,
+            angle: 0.0,
+        };
+        let mut update = |brain: &mut StateMachine, seconds: f32, senses: &Senses| {
+            brain.update(
+                Duration::from_secs_f32(seconds),
+                senses,
+                &mut transform,
+                &mut velocity,
+            )
+        };
+        assert_eq!(update(&mut brain, 0.0, &far), "wait");
+        update(&mut brain, 2.0, &far);
+        assert_eq!(update(&mut brain, 0.0, &close), "retreat");
+        assert_eq!(update(&mut brain, 0.0, &far), "wait");
+        assert_eq!(update(&mut brain, 1.6, &far), "launch");
+        assert_eq!(update(&mut brain, 0.0, &far), "wait");
+        assert_eq!(update(&mut brain, 1.6, &far), "wait");
+    }
+
+    #[test]
+    fn parses_the_arena_size() {
+        let arena = Arena::parse("# width,height\n3200, 2000\n").unwrap();
+        assert_eq!(arena.size(), Vec2::new(3200.0, 2000.0));
+        assert!(Arena::parse("# nothing here\n").is_err());
+        assert!(Arena::parse("3200\n").is_err());
+        assert!(Arena::parse("3200,wide\n").is_err());
+        assert!(Arena::parse("3200,0\n").is_err());
+        assert!(Arena::parse("3200,2000\n1600,1000\n").is_err());
+    }
+}
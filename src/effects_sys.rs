@@ -0,0 +1,286 @@
+use crate::components::*;
+use crate::events::*;
+use bevy::prelude::*;
+use rand::prelude::*;
+
+// The tint applied to a sprite while it is flashing from a hit.
+const HIT_FLASH_COLOR: Color = Color::rgb(1.0, 0.35, 0.35);
+
+// Damage to the player at or above this value shakes the camera.
+const PLAYER_DAMAGE_SHAKE_THRESHOLD: f32 = 20.0;
+
+/// Picks the color of a floating damage number based on the type of damage dealt.
+fn damage_color(damage_type: &DamageType) -> Color {
+    match damage_type {
+        DamageType::Kinetic => Color::rgb(1.0, 0.65, 0.2),
+        DamageType::Radiant => Color::rgb(0.4, 0.85, 1.0),
+    }
+}
+
+/// Reads DamageEvent events and spawns floating damage numbers and hit flashes on the damaged entities.
+pub fn spawn_damage_feedback_system(
+    mut commands: Commands,
+    mut damage_reader: EventReader<DamageEvent>,
+    q_target: Query<(&Transform, Has<Sprite>)>,
+    settings: Res<Settings>,
+) {
+    let mut rng = thread_rng();
+    for ev in damage_reader.read() {
+        // The target may have already been despawned by the time we read the event.
+        let Ok((target_transform, has_sprite)) = q_target.get(ev.target) else {
+            continue;
+        };
+
+        if settings.damage_numbers && ev.damage_value >= 0.1 {
+            // Bigger hits get bigger numbers, within reason.
+            let font_size = (14.0 + ev.damage_value.sqrt() * 3.0).clamp(14.0, 48.0);
+            let text = if ev.damage_value < 10.0 {
+                format!("{:.1}", ev.damage_value)
+            } else {
+                format!("{:.0}", ev.damage_value)
+            };
+            // Jitter the starting position a little so that rapid hits don't stack perfectly on top of each other.
+            let jitter = Vec3::new(rng.gen_range(-10.0..10.0), rng.gen_range(-5.0..5.0), 0.0);
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        text,
+                        TextStyle {
+                            font_size,
+                            color: damage_color(&ev.damage_type),
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_translation(
+                        target_transform.translation.truncate().extend(5.0) + jitter,
+                    ),
+                    ..default()
+                },
+                DamageNumber {
+                    velocity: Vec3::new(rng.gen_range(-15.0..15.0), 60.0, 0.0),
+                    timer: Timer::from_seconds(0.8, TimerMode::Once),
+                },
+            ));
+        }
+
+        if settings.hit_flash && has_sprite {
+            // The target may still be despawned later this frame, so don't panic if it's gone.
+            commands.entity(ev.target).try_insert(HitFlash {
+                timer: Timer::from_seconds(0.1, TimerMode::Once),
+            });
+        }
+    }
+}
+
+/// Drifts damage numbers upward, fades them out, and despawns them once they expire.
+pub fn update_damage_numbers_system(
+    mut commands: Commands,
+    mut q_numbers: Query<(Entity, &mut DamageNumber, &mut Transform, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (entity, mut number, mut transform, mut text) in q_numbers.iter_mut() {
+        number.timer.tick(time.delta());
+        if number.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += number.velocity * time.delta_seconds();
+        let alpha = 1.0 - number.timer.percent();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+/// Tints sprites that were recently hit, and restores their color once the flash is over.
+pub fn update_hit_flash_system(
+    mut commands: Commands,
+    mut q_flash: Query<(Entity, &mut HitFlash, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut flash, mut sprite) in q_flash.iter_mut() {
+        flash.timer.tick(time.delta());
+        if flash.timer.finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<HitFlash>();
+        } else {
+            sprite.color = HIT_FLASH_COLOR;
+        }
+    }
+}
+
+/// Shakes the camera whenever something explodes. Bigger blasts shake harder.
+pub fn detonation_impulse_system(
+    mut explosion_reader: EventReader<ExplosionEvent>,
+    mut impulse_writer: EventWriter<CameraImpulseEvent>,
+) {
+    for ev in explosion_reader.read() {
+        impulse_writer.send(CameraImpulseEvent {
+            trauma: 0.3 * ev.explosion.radius / 200.0,
+            hit_stop: 0.0,
+        });
+    }
+}
+
+/// Shakes the camera, and briefly freezes the game, when the player takes a big hit.
+pub fn player_damage_impulse_system(
+    mut damage_reader: EventReader<DamageEvent>,
+    mut impulse_writer: EventWriter<CameraImpulseEvent>,
+    q_player: Query<(), With<Player>>,
+) {
+    for ev in damage_reader.read() {
+        if ev.damage_value >= PLAYER_DAMAGE_SHAKE_THRESHOLD && q_player.contains(ev.target) {
+            impulse_writer.send(CameraImpulseEvent {
+                trauma: (ev.damage_value / 100.0).clamp(0.2, 0.8),
+                hit_stop: 0.05,
+            });
+        }
+    }
+}
+
+/// Draws each active beam as its weapon's sprite stretched from the ship's nose to whatever the beam is hitting.
+/// The beam glows hotter as it approaches overheating. Beams without an owner are cleaned up.
+pub fn beam_visual_system(
+    mut commands: Commands,
+    q_beam: Query<&BeamWeapon>,
+    mut q_visual: Query<(
+        Entity,
+        &BeamVisual,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+    q_new: Query<(Entity, &BeamWeapon), Without<HasBeamVisual>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (visual_entity, visual, mut transform, mut sprite, mut visibility) in q_visual.iter_mut() {
+        let Ok(beam) = q_beam.get(visual.owner) else {
+            commands.entity(visual_entity).despawn();
+            continue;
+        };
+        if !beam.active() {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+        let midpoint = beam.origin + beam.direction * beam.length / 2.0;
+        *transform = Transform::from_translation(midpoint.truncate().extend(1.0)).with_rotation(
+            Quat::from_rotation_arc_2d(Vec2::Y, beam.direction.truncate().normalize_or_zero()),
+        );
+        sprite.custom_size = Some(Vec2::new(beam.width, beam.length));
+        let heat = beam.heat.fraction();
+        sprite.color = Color::rgb(1.0, 1.0 - 0.6 * heat, 1.0 - 0.6 * heat);
+    }
+
+    // Give any new beam weapons a sprite to draw with.
+    for (owner, beam) in q_new.iter() {
+        commands.spawn((
+            SpriteBundle {
+                texture: asset_server.load(&beam.sprite_path),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            BeamVisual { owner },
+        ));
+        commands.entity(owner).insert(HasBeamVisual {});
+    }
+}
+
+/// Shows whether mines are armed. Unarmed mines are a dull gray, and armed mines blink in their kind's color.
+pub fn mine_blink_system(
+    mut q_mine: Query<(&mut Mine, &mut Sprite), Without<HitFlash>>,
+    time: Res<Time>,
+) {
+    for (mut mine, mut sprite) in q_mine.iter_mut() {
+        if !mine.arming_timer.finished() {
+            sprite.color = Color::GRAY;
+            continue;
+        }
+        mine.blink_timer.tick(time.delta());
+        sprite.color = if mine.blink_timer.percent() < 0.5 {
+            mine.kind.color()
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+/// Marks each boss phase transition: a burst over the boss, a long flash, a shake, and the phase's name floating up
+/// from it. Switches to the new phase's track, and stops the music once no boss is left.
+pub fn boss_phase_effects_system(
+    mut commands: Commands,
+    mut phase_reader: EventReader<BossPhaseEvent>,
+    q_music: Query<Entity, With<BossMusic>>,
+    q_boss: Query<(), With<BossAI>>,
+    mut impulse_writer: EventWriter<CameraImpulseEvent>,
+    asset_server: Res<AssetServer>,
+) {
+    for ev in phase_reader.read() {
+        impulse_writer.send(CameraImpulseEvent {
+            trauma: 0.6,
+            hit_stop: 0.15,
+        });
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load("sounds/explosionCrunch_003.ogg"),
+                ..default()
+            },
+            SelfDestruct {
+                cd_timer: Timer::from_seconds(1.0, TimerMode::Once),
+            },
+        ));
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(ev.position.truncate().extend(2.0))
+                    .with_scale(GLOBAL_RESCALE_V * 3.0),
+                texture: asset_server.load("sprites/effects/explosion_tmp.png"),
+                ..default()
+            },
+            SelfDestruct {
+                cd_timer: Timer::from_seconds(0.5, TimerMode::Once),
+            },
+        ));
+        // The boss may have gone down on the same frame.
+        commands.entity(ev.boss).try_insert(HitFlash {
+            timer: Timer::from_seconds(0.5, TimerMode::Once),
+        });
+        // Floats up and fades out like a damage number.
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    ev.name.to_uppercase(),
+                    TextStyle {
+                        font_size: 48.0,
+                        color: Color::RED,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(ev.position.truncate().extend(5.0)),
+                ..default()
+            },
+            DamageNumber {
+                velocity: Vec3::new(0.0, 40.0, 0.0),
+                timer: Timer::from_seconds(2.0, TimerMode::Once),
+            },
+        ));
+
+        if let Some(track) = ev.music {
+            for music in q_music.iter() {
+                commands.entity(music).despawn();
+            }
+            commands.spawn((
+                AudioBundle {
+                    source: asset_server.load(track),
+                    settings: PlaybackSettings::LOOP,
+                },
+                BossMusic {},
+            ));
+        }
+    }
+
+    if q_boss.is_empty() {
+        for music in q_music.iter() {
+            commands.entity(music).despawn();
+        }
+    }
+}
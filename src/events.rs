@@ -8,6 +8,13 @@ pub struct DamageEvent {
     pub damage_type: DamageType,
 }
 
+/// Pushes an entity. The impulse is momentum in kg * m/s, so heavier entities are pushed less.
+#[derive(Event)]
+pub struct ImpulseEvent {
+    pub target: Entity,
+    pub impulse: Vec3,
+}
+
 #[derive(Event)]
 pub struct CollisionEvent {
     pub entity: Entity,
@@ -20,16 +27,52 @@ pub struct CollisionEvent {
 #[derive(Event)]
 pub struct SpawnGuidedMissileEvent {
     pub transform: Transform,
+    // The entity the missile should track. If None, the missile flies straight.
+    pub target: Option<Entity>,
+    pub seeker: Seeker,
+    // The side of whoever launched it.
+    pub faction: Option<Faction>,
 }
 
 #[derive(Event)]
 pub struct SpawnMineEvent {
     pub transform: Transform,
+    pub velocity: Vec3,
+    pub mine: Mine,
+    // The side of whoever laid it.
+    pub faction: Option<Faction>,
 }
 
-// Missile detonation events record the missile entity that detonated.
-// The event handling system will despawn the missile and spawn an explosion at its location.
+// Missile detonation events record the missile or mine entity that detonated.
+// The event handling system will despawn it and spawn an explosion at its location.
 #[derive(Event)]
 pub struct MissileDetonationEvent {
     pub entity: Entity,
 }
+
+// Explosion events damage everything with Clipping within the radius of the position that the faction can harm, and
+// play the explosion sound and visuals. Missiles, mines and torpedoes all explode through this event.
+#[derive(Event)]
+pub struct ExplosionEvent {
+    pub position: Vec3,
+    pub explosion: Explosion,
+    pub faction: Option<Faction>,
+}
+
+// Camera impulse events add trauma to the main camera's shake, and can optionally freeze the game for a moment.
+#[derive(Event)]
+pub struct CameraImpulseEvent {
+    // Trauma to add, from 0.0 to 1.0.
+    pub trauma: f32,
+    // Seconds of hit-stop. 0.0 for none.
+    pub hit_stop: f32,
+}
+
+// Boss phase events record a boss moving on to its next phase. Music and visual effects hang off of them.
+#[derive(Event)]
+pub struct BossPhaseEvent {
+    pub boss: Entity,
+    pub position: Vec3,
+    pub name: &'static str,
+    pub music: Option<&'static str>,
+}
@@ -0,0 +1,169 @@
+//! This file contains helpers for doing geometry in the arena. The arena wraps around at its edges, making it a
+//! torus, so the shortest path between two points may cross an edge. Anything that measures distance or direction
+//! between two entities should go through these helpers rather than subtracting translations directly.
+
+use crate::components::*;
+use bevy::prelude::*;
+
+/// Returns the shortest displacement from `from` to `to`, taking wrapping across the arena's edges into account.
+/// The z component is left as a plain difference.
+pub fn wrapped_delta(from: Vec3, to: Vec3, arena: &Arena) -> Vec3 {
+    let mut delta = to - from;
+    if delta.x > arena.width / 2.0 {
+        delta.x -= arena.width;
+    } else if delta.x < -arena.width / 2.0 {
+        delta.x += arena.width;
+    }
+    if delta.y > arena.height / 2.0 {
+        delta.y -= arena.height;
+    } else if delta.y < -arena.height / 2.0 {
+        delta.y += arena.height;
+    }
+    delta
+}
+
+/// Returns the shortest distance between two points, taking wrapping across the arena's edges into account.
+pub fn wrapped_distance(a: Vec3, b: Vec3, arena: &Arena) -> f32 {
+    wrapped_delta(a, b, arena).truncate().length()
+}
+
+/// Returns the unit vector pointing along the shortest path from `from` to `to`.
+pub fn wrapped_direction(from: Vec3, to: Vec3, arena: &Arena) -> Vec3 {
+    wrapped_delta(from, to, arena)
+        .truncate()
+        .normalize_or_zero()
+        .extend(0.0)
+}
+
+/// Returns the position of `to` as seen from `from`, i.e. moved onto the same side of any arena edge as `from`.
+/// Useful for drawing or aiming at an entity that is just across the seam.
+pub fn unwrapped_position(from: Vec3, to: Vec3, arena: &Arena) -> Vec3 {
+    from + wrapped_delta(from, to, arena)
+}
+
+/// Wraps a position back into the arena, preserving how far it went past the edge.
+pub fn wrap_position(position: Vec3, arena: &Arena) -> Vec3 {
+    Vec3 {
+        x: position.x.rem_euclid(arena.width),
+        y: position.y.rem_euclid(arena.height),
+        z: position.z,
+    }
+}
+
+/// Returns whether the straight line from `from` to `to` passes through the circle at `center`. Only the part of the
+/// line in front of `from` counts, so a circle that `from` sits inside blocks only what is behind it.
+pub fn segment_blocked(from: Vec3, to: Vec3, center: Vec3, radius: f32, arena: &Arena) -> bool {
+    let segment = wrapped_delta(from, to, arena).truncate();
+    let length = segment.length();
+    if length == 0.0 {
+        return false;
+    }
+    let direction = segment / length;
+    let to_center = wrapped_delta(from, center, arena).truncate();
+    let along = to_center.dot(direction);
+    if along <= 0.0 || along >= length {
+        return false;
+    }
+    (to_center - direction * along).length() < radius
+}
+
+/// Returns where a projectile fired from `from` at `speed` would meet a target at `to` moving at `target_velocity`,
+/// as seen from `from`. Speeds are in the same units, per second. Returns None if the projectile can never catch up.
+pub fn intercept_point(
+    from: Vec3,
+    to: Vec3,
+    target_velocity: Vec3,
+    speed: f32,
+    arena: &Arena,
+) -> Option<Vec3> {
+    // Solve |delta + target_velocity * t| = speed * t for the earliest time t > 0.
+    let delta = wrapped_delta(from, to, arena).truncate();
+    let velocity = target_velocity.truncate();
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2.0 * delta.dot(velocity);
+    let c = delta.length_squared();
+    let t = if a.abs() < f32::EPSILON {
+        // The projectile is exactly as fast as the target, so there is at most one solution.
+        (b < 0.0).then(|| -c / b)?
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        let (t1, t2) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+        match (t1 > 0.0, t2 > 0.0) {
+            (true, true) => t1.min(t2),
+            (true, false) => t1,
+            (false, true) => t2,
+            (false, false) => return None,
+        }
+    };
+    Some(from + (delta + velocity * t).extend(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARENA: Arena = Arena {
+        width: 1000.0,
+        height: 1000.0,
+    };
+
+    #[test]
+    fn intercepts_a_stationary_target_where_it_is() {
+        let point = intercept_point(
+            Vec3::ZERO,
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::ZERO,
+            50.0,
+            &ARENA,
+        );
+        assert_eq!(point, Some(Vec3::new(100.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn projectile_and_target_arrive_together() {
+        let to = Vec3::new(200.0, 0.0, 0.0);
+        let velocity = Vec3::new(0.0, 30.0, 0.0);
+        let speed = 50.0;
+        let point = intercept_point(Vec3::ZERO, to, velocity, speed, &ARENA).unwrap();
+        let projectile_time = point.length() / speed;
+        let target_time = (point - to).length() / velocity.length();
+        assert!((projectile_time - target_time).abs() < 1e-3);
+    }
+
+    #[test]
+    fn equal_speed_target_is_caught_only_when_closing() {
+        let to = Vec3::new(100.0, 0.0, 0.0);
+        let closing = intercept_point(Vec3::ZERO, to, Vec3::new(-10.0, 0.0, 0.0), 10.0, &ARENA);
+        assert_eq!(closing, Some(Vec3::new(50.0, 0.0, 0.0)));
+        let fleeing = intercept_point(Vec3::ZERO, to, Vec3::new(10.0, 0.0, 0.0), 10.0, &ARENA);
+        assert_eq!(fleeing, None);
+    }
+
+    #[test]
+    fn faster_target_running_away_is_uncatchable() {
+        let point = intercept_point(
+            Vec3::ZERO,
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            20.0,
+            &ARENA,
+        );
+        assert_eq!(point, None);
+    }
+
+    #[test]
+    fn aims_across_the_arena_edge() {
+        let point = intercept_point(
+            Vec3::new(950.0, 500.0, 0.0),
+            Vec3::new(50.0, 500.0, 0.0),
+            Vec3::ZERO,
+            10.0,
+            &ARENA,
+        );
+        assert_eq!(point, Some(Vec3::new(1050.0, 500.0, 0.0)));
+    }
+}
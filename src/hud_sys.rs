@@ -0,0 +1,520 @@
+use crate::components::*;
+use crate::geometry::*;
+use bevy::ecs::query::WorldQuery;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use std::f32::consts::PI;
+
+// Size of the minimap in pixels, and its distance from the corner of the screen.
+const MINIMAP_SIZE: Vec2 = Vec2 { x: 240.0, y: 150.0 };
+const MINIMAP_MARGIN: f32 = 16.0;
+
+// How far from the screen edge the off-screen indicators are drawn, and how many enemies get one.
+const INDICATOR_MARGIN: f32 = 24.0;
+const INDICATOR_SIZE: f32 = 14.0;
+const MAX_ENEMY_INDICATORS: usize = 3;
+
+/// The AI markers that an enemy may carry, used to tell the archetypes apart on the HUD.
+#[derive(WorldQuery)]
+pub struct AiMarkers {
+    turret: Has<TurretAI>,
+    drone: Has<DroneAI>,
+    speedy: Has<SpeedyAI>,
+    rammer: Has<RammerAI>,
+    picket: Has<PicketAI>,
+    minelayer: Has<MineLayerAI>,
+    boss: Has<BossAI>,
+}
+
+/// Everything the minimap needs to know about a non-enemy entity to draw it.
+#[derive(WorldQuery)]
+pub struct MinimapBlip {
+    transform: &'static Transform,
+    entity_type: &'static EntityType,
+    collision_box: &'static CollisionBox,
+    is_player: Has<Player>,
+    is_guided: Has<Thruster>,
+}
+
+impl AiMarkersItem<'_> {
+    /// The color used to draw this enemy's archetype on the minimap.
+    fn color(&self) -> Color {
+        if self.boss {
+            Color::PURPLE
+        } else if self.picket {
+            Color::FUCHSIA
+        } else if self.rammer {
+            Color::CRIMSON
+        } else if self.speedy {
+            Color::ORANGE_RED
+        } else if self.drone {
+            Color::GOLD
+        } else if self.minelayer {
+            Color::TEAL
+        } else if self.turret {
+            Color::SALMON
+        } else {
+            Color::RED
+        }
+    }
+}
+
+/// The main camera and the window it renders to, used to work out what part of the world is on screen.
+#[derive(SystemParam)]
+pub struct CameraView<'w, 's> {
+    q_camera: Query<'w, 's, &'static Transform, With<MainCamera>>,
+    q_window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl CameraView<'_, '_> {
+    /// Returns the world-space rectangle currently visible through the main camera.
+    fn rect(&self) -> Option<Rect> {
+        let (Ok(camera_transform), Ok(window)) =
+            (self.q_camera.get_single(), self.q_window.get_single())
+        else {
+            return None;
+        };
+        Some(Rect::from_center_size(
+            camera_transform.translation.truncate(),
+            Vec2::new(window.width(), window.height()),
+        ))
+    }
+}
+
+/// Draws a minimap in the bottom-right corner of the screen showing the player, enemies, missiles, mines and
+/// asteroids across the whole world.
+pub fn minimap_system(
+    mut gizmos: Gizmos,
+    settings: Res<Settings>,
+    camera_view: CameraView,
+    q_enemy: Query<(&Transform, AiMarkers), With<Enemy>>,
+    q_other: Query<MinimapBlip, Without<Enemy>>,
+    arena: Res<Arena>,
+) {
+    if !settings.minimap {
+        return;
+    }
+    let Some(view) = camera_view.rect() else {
+        return;
+    };
+
+    let world_size = arena.size();
+    let map_center = Vec2::new(
+        view.max.x - MINIMAP_MARGIN - MINIMAP_SIZE.x / 2.0,
+        view.min.y + MINIMAP_MARGIN + MINIMAP_SIZE.y / 2.0,
+    );
+    let map_origin = map_center - MINIMAP_SIZE / 2.0;
+    let to_map = |world: Vec3| map_origin + world.truncate() / world_size * MINIMAP_SIZE;
+
+    gizmos.rect_2d(map_center, 0.0, MINIMAP_SIZE, Color::GRAY);
+    // Outline the part of the world that is currently on screen.
+    gizmos.rect_2d(
+        to_map(view.center().extend(0.0)),
+        0.0,
+        view.size() / world_size * MINIMAP_SIZE,
+        Color::DARK_GRAY,
+    );
+
+    for blip in q_other.iter() {
+        let position = to_map(blip.transform.translation);
+        if blip.is_player {
+            gizmos.circle_2d(position, 3.0, Color::GREEN).segments(8);
+        } else if *blip.entity_type == EntityType::Asteroid {
+            let radius = (blip.collision_box.width_radius / world_size.x * MINIMAP_SIZE.x).max(1.5);
+            gizmos.circle_2d(position, radius, Color::GRAY).segments(8);
+        } else if *blip.entity_type == EntityType::Missile {
+            // Guided missiles have thrusters, mines just sit there.
+            if blip.is_guided {
+                gizmos.line_2d(position - Vec2::ONE, position + Vec2::ONE, Color::ORANGE);
+                gizmos.line_2d(
+                    position + Vec2::new(-1.0, 1.0),
+                    position + Vec2::new(1.0, -1.0),
+                    Color::ORANGE,
+                );
+            } else {
+                gizmos.rect_2d(position, PI / 4.0, Vec2::splat(2.0), Color::YELLOW);
+            }
+        }
+    }
+
+    for (transform, markers) in q_enemy.iter() {
+        gizmos
+            .circle_2d(to_map(transform.translation), 2.5, markers.color())
+            .segments(6);
+    }
+}
+
+// Guided missiles in flight.
+type LiveMissile = (With<Missile>, With<Thruster>);
+
+/// Draws arrows at the edge of the screen pointing toward the nearest off-screen enemies and any off-screen
+/// guided missiles tracking the player.
+pub fn offscreen_indicator_system(
+    mut gizmos: Gizmos,
+    settings: Res<Settings>,
+    camera_view: CameraView,
+    q_player: Query<(Entity, &Transform), With<Player>>,
+    q_enemy: Query<&Transform, With<Enemy>>,
+    q_missile: Query<(&Transform, &AiTarget), LiveMissile>,
+    arena: Res<Arena>,
+) {
+    if !settings.edge_indicators {
+        return;
+    }
+    let Some(view) = camera_view.rect() else {
+        return;
+    };
+    let player = q_player.get_single().ok();
+    let origin = player
+        .map(|(_, t)| t.translation.truncate())
+        .unwrap_or(view.center());
+    // Point the way to each target along the shortest path, which may cross the edge of the arena.
+    let seen_from_view = |t: &Transform| {
+        unwrapped_position(view.center().extend(0.0), t.translation, &arena).truncate()
+    };
+
+    let mut offscreen_enemies = q_enemy
+        .iter()
+        .map(seen_from_view)
+        .filter(|p| !view.contains(*p))
+        .collect::<Vec<Vec2>>();
+    offscreen_enemies.sort_by(|a, b| a.distance(origin).total_cmp(&b.distance(origin)));
+
+    for target in offscreen_enemies.iter().take(MAX_ENEMY_INDICATORS) {
+        draw_edge_arrow(&mut gizmos, view, *target, Color::RED);
+    }
+    // The player's own missiles aren't a threat.
+    let incoming = q_missile
+        .iter()
+        .filter(|(_, ai_target)| player.is_some_and(|(player, _)| ai_target.0 == player));
+    for (missile_transform, _) in incoming {
+        let target = seen_from_view(missile_transform);
+        if !view.contains(target) {
+            draw_edge_arrow(&mut gizmos, view, target, Color::ORANGE);
+        }
+    }
+}
+
+/// Draws a triangle just inside the edge of the view, on the line from the view's center to the target,
+/// pointing toward the target.
+fn draw_edge_arrow(gizmos: &mut Gizmos, view: Rect, target: Vec2, color: Color) {
+    let center = view.center();
+    let dir = (target - center).normalize_or_zero();
+    if dir == Vec2::ZERO {
+        return;
+    }
+    // Scale the direction so that it just touches the inset edge of the view on whichever axis it hits first.
+    let half = view.half_size() - Vec2::splat(INDICATOR_MARGIN);
+    let scale = (half.x / dir.x.abs()).min(half.y / dir.y.abs());
+    let tip = center + dir * scale;
+    let perp = dir.perp();
+    let base = tip - dir * INDICATOR_SIZE;
+    let left = base + perp * INDICATOR_SIZE * 0.5;
+    let right = base - perp * INDICATOR_SIZE * 0.5;
+    gizmos.linestrip_2d([tip, left, right, tip], color);
+}
+
+/// Draws the player's lock-on reticles: a shrinking circle around the enemy being locked onto, and a diamond
+/// around the locked target.
+pub fn target_lock_hud_system(
+    mut gizmos: Gizmos,
+    q_player: Query<&TargetLock, With<Player>>,
+    q_target: Query<&Transform, With<Enemy>>,
+) {
+    let Ok(lock) = q_player.get_single() else {
+        return;
+    };
+    if let Some(candidate) = lock.candidate.filter(|c| Some(*c) != lock.target) {
+        if let Ok(transform) = q_target.get(candidate) {
+            let radius = 60.0 - 35.0 * lock.lock_timer.percent();
+            gizmos
+                .circle_2d(transform.translation.truncate(), radius, Color::YELLOW)
+                .segments(24);
+        }
+    }
+    if let Some(target) = lock.target {
+        if let Ok(transform) = q_target.get(target) {
+            gizmos.rect_2d(
+                transform.translation.truncate(),
+                PI / 4.0,
+                Vec2::splat(40.0),
+                Color::RED,
+            );
+        }
+    }
+}
+
+/// Spawns the HUD text in the corners of the screen.
+pub fn spawn_hud_system(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        CountermeasureHud {},
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        WeaponHud {},
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        ScoreHud {},
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(35.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        AdaptiveOverlay {},
+    ));
+}
+
+/// Debug overlay showing the adaptive difficulty modifier, what it is doing to the game, and the window being judged.
+pub fn adaptive_overlay_system(
+    settings: Res<Settings>,
+    adaptive: Res<AdaptiveDifficulty>,
+    mut q_text: Query<&mut Text, With<AdaptiveOverlay>>,
+) {
+    let Ok(mut text) = q_text.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = if !settings.adaptive_overlay {
+        String::new()
+    } else if !adaptive.enabled {
+        "Adaptive: off".to_string()
+    } else {
+        format!(
+            "Adaptive: x{:.2}\nWave delay x{:.2}, accuracy x{:.2}, drops {:.0}%\nDamage taken {:.0}, kills {}{}\nNext nudge in {:.1}s",
+            adaptive.modifier,
+            adaptive.spawn_delay(),
+            adaptive.accuracy(),
+            100.0 * adaptive.drop_chance(),
+            adaptive.damage_taken,
+            adaptive.kills,
+            if adaptive.near_death { ", near death" } else { "" },
+            adaptive.window.remaining_secs()
+        )
+    };
+}
+
+/// Shows the score and the difficulty it is being earned on in the top-right corner of the screen.
+pub fn score_hud_system(
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    mut q_text: Query<&mut Text, With<ScoreHud>>,
+) {
+    let Ok(mut text) = q_text.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = format!("Score: {} ({})", score.points, difficulty.level.name());
+}
+
+/// Shows the selected countermeasure and how many charges are left, and how hot the player's beam is running.
+pub fn countermeasure_hud_system(
+    q_player: Query<(&CountermeasureLauncher, Option<&BeamWeapon>), With<Player>>,
+    mut q_text: Query<&mut Text, With<CountermeasureHud>>,
+) {
+    let Ok(mut text) = q_text.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = match q_player.get_single() {
+        Ok((launcher, beam)) => {
+            let mut value = format!(
+                "{}: {}/{}",
+                launcher.kind.name(),
+                launcher.charges,
+                launcher.max_charges
+            );
+            if let Some(beam) = beam {
+                value += &format!("\n{}: {}", beam.name, heat_status(&beam.heat));
+            }
+            value
+        }
+        Err(_) => String::new(),
+    };
+}
+
+/// Describes how hot a heat sink is running, for beams and heat-limited weapons alike.
+fn heat_status(sink: &HeatSink) -> String {
+    if sink.overheated {
+        "OVERHEATED".to_string()
+    } else {
+        format!("heat {:.0}%", 100.0 * sink.fraction())
+    }
+}
+
+/// Describes how much a weapon has left to fire, or None for weapons with nothing to show.
+fn weapon_status(weapon: &WeaponSystem) -> Option<String> {
+    let status = match &weapon.resource {
+        WeaponResource::Unlimited => return None,
+        WeaponResource::Ammo {
+            reloading: true, ..
+        } => "RELOADING".to_string(),
+        WeaponResource::Ammo {
+            rounds, max_rounds, ..
+        } => format!("{}/{}", rounds, max_rounds),
+        WeaponResource::Heat { sink, .. } => heat_status(sink),
+        WeaponResource::Energy { cost } => format!("{:.0} energy/shot", cost),
+    };
+    Some(format!("{}: {}", weapon.name, status))
+}
+
+/// Shows the player's ammo, heat and reactor energy in the bottom-left corner of the screen.
+pub fn weapon_hud_system(
+    q_player: Query<(&Ship, Option<&Reactor>), With<Player>>,
+    mut q_text: Query<&mut Text, With<WeaponHud>>,
+) {
+    let Ok(mut text) = q_text.get_single_mut() else {
+        return;
+    };
+    let Ok((ship, reactor)) = q_player.get_single() else {
+        text.sections[0].value = String::new();
+        return;
+    };
+    let mut lines = [
+        &ship.primary_weapon,
+        &ship.secondary_weapon,
+        &ship.tertiary_weapon,
+    ]
+    .into_iter()
+    .filter_map(weapon_status)
+    .collect::<Vec<String>>();
+    if let Some(reactor) = reactor {
+        lines.push(format!(
+            "Reactor: {:.0}/{:.0}",
+            reactor.energy, reactor.max_energy
+        ));
+    }
+    text.sections[0].value = lines.join("\n");
+}
+
+type AiBrain<'a> = (
+    Option<&'a StateMachine>,
+    Option<&'a BehaviorTree>,
+    Option<&'a SquadMember>,
+    &'a Transform,
+);
+type Unlabelled = (
+    Or<(With<StateMachine>, With<BehaviorTree>)>,
+    Without<HasAiStateLabel>,
+);
+type StateLabel<'a> = (
+    Entity,
+    &'a AiStateLabel,
+    &'a mut Transform,
+    &'a mut Text,
+    &'a mut Visibility,
+);
+
+/// Labels every AI ship with its current state, how long it has been in it, and the state it came from.
+/// Ships driven by a behavior tree are labelled with the tree they run instead. Squad members also show their
+/// squad, and whether they lead it.
+pub fn ai_state_label_system(
+    mut commands: Commands,
+    q_brain: Query<AiBrain, Without<AiStateLabel>>,
+    mut q_label: Query<StateLabel>,
+    q_new: Query<Entity, Unlabelled>,
+    q_squad: Query<&Squad>,
+    settings: Res<Settings>,
+) {
+    for (label_entity, label, mut transform, mut text, mut visibility) in q_label.iter_mut() {
+        let Ok((brain, tree, member, owner_transform)) = q_brain.get(label.owner) else {
+            commands.entity(label_entity).despawn();
+            continue;
+        };
+        if !settings.ai_states {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+        transform.translation = owner_transform.translation.truncate().extend(5.0) + Vec3::Y * 30.0;
+        text.sections[0].value = match (brain, tree) {
+            (Some(brain), _) => match brain.previous_state() {
+                Some(previous) => format!(
+                    "{} {:.1}s (from {})",
+                    brain.state(),
+                    brain.time_in_state(),
+                    previous
+                ),
+                None => format!("{} {:.1}s", brain.state(), brain.time_in_state()),
+            },
+            (None, Some(tree)) => format!("{} tree", tree.name),
+            (None, None) => String::new(),
+        };
+        if let Some(squad) = member.and_then(|m| q_squad.get(m.squad).ok()) {
+            let role = if squad.leader == label.owner {
+                "leader"
+            } else {
+                "wing"
+            };
+            text.sections[0].value += &format!(" [{} {}]", squad.name, role);
+        }
+    }
+
+    for owner in q_new.iter() {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: Color::CYAN,
+                        ..default()
+                    },
+                ),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            AiStateLabel { owner },
+        ));
+        commands.entity(owner).try_insert(HasAiStateLabel {});
+    }
+}
@@ -1,12 +1,18 @@
 mod ai_sys;
 mod camera_sys;
 mod components;
+mod effects_sys;
 mod events;
+mod geometry;
+mod hud_sys;
+mod menu_sys;
 mod physics_sys;
 mod player_sys;
+mod settings_sys;
 mod ship_parts;
 mod ships;
 mod spawn_sys;
+mod steering;
 mod traits;
 
 use ai_sys::*;
@@ -14,9 +20,13 @@ use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::prelude::*;
 use camera_sys::*;
 use components::*;
+use effects_sys::*;
 use events::*;
+use hud_sys::*;
+use menu_sys::*;
 use physics_sys::*;
 use player_sys::*;
+use settings_sys::*;
 use spawn_sys::*;
 use std::fs;
 
@@ -30,6 +40,7 @@ fn main() {
         ))
         // Resources
         .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
+        .insert_resource(Arena::load("assets/arena.txt"))
         .insert_resource(WorldCoords {
             coords: Vec2::default(),
         })
@@ -37,55 +48,98 @@ fn main() {
             txt: fs::read_to_string("assets/levels/level_script.txt")
                 .expect("Expected to find level_script.txt in assets/levels/"),
         })
+        .insert_resource(BehaviorLibrary::load("assets/behaviors"))
+        .insert_resource(SquadLibrary::load("assets/squads"))
+        .insert_resource(FactionTable::load("assets/factions.txt"))
+        .init_resource::<Settings>()
+        .init_resource::<HitStop>()
+        .init_resource::<TargetCache>()
+        .init_resource::<ObstacleGrid>()
+        .init_resource::<Difficulty>()
+        .init_resource::<MenuCursor>()
+        .init_resource::<Score>()
+        .init_resource::<AdaptiveDifficulty>()
+        .insert_resource(HighScores::load("highscores.txt"))
         .insert_resource(CurrentScriptLine { line_num: 0 })
         .insert_resource(ScriptTimer {
             delay: Timer::from_seconds(0.0, TimerMode::Once),
         })
+        .add_state::<AppState>()
         // Startup Systems
+        .add_systems(Startup, spawn_camera_system)
+        // Menu Systems
+        .add_systems(OnEnter(AppState::MainMenu), spawn_main_menu_system)
+        .add_systems(OnExit(AppState::MainMenu), despawn_main_menu_system)
         .add_systems(
-            Startup,
+            Update,
+            main_menu_system.run_if(in_state(AppState::MainMenu)),
+        )
+        // Game Start and End Systems
+        .add_systems(
+            OnEnter(AppState::InGame),
             (
-                spawn_camera_system,
-                setup_background_stars_system.after(spawn_camera_system),
-                spawn_player_system.after(spawn_camera_system),
-                spawn_asteroid_system.after(spawn_camera_system),
-            ),
+                reset_game_system,
+                setup_background_stars_system,
+                spawn_player_system,
+                spawn_asteroid_system,
+                spawn_hud_system,
+            )
+                .chain(),
         )
+        .add_systems(OnExit(AppState::InGame), cleanup_game_system)
         // Register Events
         .add_event::<DamageEvent>()
         .add_event::<CollisionEvent>()
         .add_event::<SpawnGuidedMissileEvent>()
         .add_event::<MissileDetonationEvent>()
+        .add_event::<ExplosionEvent>()
         .add_event::<SpawnMineEvent>()
+        .add_event::<CameraImpulseEvent>()
+        .add_event::<ImpulseEvent>()
+        .add_event::<BossPhaseEvent>()
         // Update Systems
         .add_systems(
             Update,
             (
                 read_script_system,
                 move_stars_system,
-                mouse_world_coords_system,
+                follow_camera_system.after(camera_shake_system),
+                mouse_world_coords_system.after(follow_camera_system),
                 player_weapons_system,
                 wrap_clipping_location_system,
                 despawn_dead_system,
                 test_weapon_toggle,
-                turret_ai_system,
-                speedy_ai_system,
-                drone_ai_system,
-                rammer_ai_system,
-                picket_ai_system,
-                minelayer_ai_system,
+                turret_ai_system
+                    .after(ai_target_selection_system)
+                    .run_if(hit_stop_inactive),
+                speedy_ai_system
+                    .after(squad_system)
+                    .run_if(hit_stop_inactive),
+                drone_ai_system
+                    .after(squad_system)
+                    .run_if(hit_stop_inactive),
+                rammer_ai_system
+                    .after(squad_system)
+                    .run_if(hit_stop_inactive),
+                picket_ai_system
+                    .after(ai_target_selection_system)
+                    .run_if(hit_stop_inactive),
+                minelayer_ai_system.run_if(hit_stop_inactive),
                 handle_self_destruct_system,
                 spawn_missile_system,
                 spawn_mine_system,
-                guided_missile_ai_system,
-                boss_ai_system,
-            ),
+                guided_missile_ai_system.run_if(hit_stop_inactive),
+                boss_ai_system
+                    .after(ai_target_selection_system)
+                    .run_if(hit_stop_inactive),
+            )
+                .run_if(in_state(AppState::InGame)),
         )
         .add_systems(
             Update,
             (
-                move_projectiles_system,
-                movement_system,
+                move_projectiles_system.run_if(hit_stop_inactive),
+                movement_system.run_if(hit_stop_inactive),
                 update_player_velocity_system,
                 inflict_damage_system,
                 collision_calculation_system,
@@ -93,9 +147,73 @@ fn main() {
                 check_projectile_collisions,
                 check_missile_collisions_system,
                 handle_denotation_event_system,
-            ),
+                handle_explosion_event_system,
+                homing_projectile_system,
+                behavior_tree_ai_system
+                    .after(ai_target_selection_system)
+                    .run_if(hit_stop_inactive),
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                settings_hotkeys_system,
+                spawn_damage_feedback_system,
+                update_damage_numbers_system,
+                update_hit_flash_system,
+                minimap_system,
+                target_lock_hud_system,
+                offscreen_indicator_system,
+                detonation_impulse_system,
+                player_damage_impulse_system,
+                camera_impulse_system,
+                camera_shake_system.after(camera_impulse_system),
+                hit_stop_system,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                player_target_lock_system.after(mouse_world_coords_system),
+                player_missile_launch_system.after(player_target_lock_system),
+                point_defense_system,
+                player_countermeasure_system,
+                decoy_system,
+                countermeasure_hud_system,
+                player_beam_system,
+                beam_weapon_system.after(player_beam_system),
+                beam_visual_system.after(beam_weapon_system),
+                reactor_recharge_system,
+                weapon_hud_system,
+                obstacle_grid_system,
+                ai_target_selection_system.after(obstacle_grid_system),
+                squad_system.after(ai_target_selection_system),
+                apply_impulse_system,
+                mine_trigger_system,
+                gravity_well_system,
+                emp_recovery_system,
+                mine_blink_system.after(mine_trigger_system),
+                ai_state_label_system,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                apply_difficulty_system,
+                adaptive_difficulty_system,
+                player_pickup_system,
+                score_hud_system,
+                adaptive_overlay_system,
+                boss_phase_effects_system.after(boss_ai_system),
+                game_over_system,
+                tick_timers,
+                tick_module_timers,
+            )
+                .run_if(in_state(AppState::InGame)),
         )
-        .add_systems(Update, tick_timers)
         .run();
 }
 
@@ -107,9 +225,9 @@ fn tick_timers(
     time: Res<Time>,
 ) {
     for mut ship in ship_query.iter_mut() {
-        ship.primary_weapon.cd_timer.tick(time.delta());
-        ship.secondary_weapon.cd_timer.tick(time.delta());
-        ship.tertiary_weapon.cd_timer.tick(time.delta());
+        ship.primary_weapon.tick(time.delta());
+        ship.secondary_weapon.tick(time.delta());
+        ship.tertiary_weapon.tick(time.delta());
     }
     for mut clipping in clipping_query.iter_mut() {
         clipping.cd_timer.tick(time.delta());
@@ -119,3 +237,21 @@ fn tick_timers(
     }
     script_timer.delay.tick(time.delta());
 }
+
+/// Ticks the cooldowns of optional ship modules.
+fn tick_module_timers(
+    mut launcher_query: Query<&mut MissileLauncher>,
+    mut point_defense_query: Query<&mut PointDefense>,
+    mut countermeasure_query: Query<&mut CountermeasureLauncher>,
+    time: Res<Time>,
+) {
+    for mut launcher in launcher_query.iter_mut() {
+        launcher.cd_timer.tick(time.delta());
+    }
+    for mut point_defense in point_defense_query.iter_mut() {
+        point_defense.weapon.tick(time.delta());
+    }
+    for mut countermeasure in countermeasure_query.iter_mut() {
+        countermeasure.cd_timer.tick(time.delta());
+    }
+}
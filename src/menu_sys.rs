@@ -0,0 +1,186 @@
+use crate::components::*;
+use bevy::prelude::*;
+
+/// Spawns the main menu text in the middle of the screen.
+pub fn spawn_main_menu_system(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(20.0),
+            left: Val::Percent(35.0),
+            ..default()
+        }),
+        MainMenuText {},
+    ));
+}
+
+pub fn despawn_main_menu_system(mut commands: Commands, q_menu: Query<Entity, With<MainMenuText>>) {
+    for entity in q_menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Lets the player pick a difficulty and start a game.
+/// 1-4 or Up/Down: Pick a difficulty. On custom, Up/Down also move through its values.
+/// Left/Right: Adjust the highlighted custom value
+/// A: Toggle the adaptive mode
+/// Enter: Start the game
+pub fn main_menu_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut cursor: ResMut<MenuCursor>,
+    mut difficulty: ResMut<Difficulty>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+    high_scores: Res<HighScores>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut q_text: Query<&mut Text, With<MainMenuText>>,
+) {
+    let levels = DifficultyLevel::ALL.len();
+    let custom = difficulty.level == DifficultyLevel::Custom;
+    let rows = if custom {
+        levels + difficulty.fields_mut().len()
+    } else {
+        levels
+    };
+
+    for (i, key) in [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4]
+        .into_iter()
+        .enumerate()
+    {
+        if keyboard_input.just_pressed(key) {
+            cursor.row = i;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        cursor.row = cursor.row.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        cursor.row = (cursor.row + 1).min(rows - 1);
+    }
+
+    // Highlighting a level picks it. The custom values are kept, so they can be tuned starting from any preset.
+    if cursor.row < levels {
+        let level = DifficultyLevel::ALL[cursor.row];
+        if level != difficulty.level {
+            *difficulty = match level {
+                DifficultyLevel::Custom => Difficulty {
+                    level,
+                    ..*difficulty
+                },
+                _ => Difficulty::preset(level),
+            };
+        }
+    } else {
+        let step = if keyboard_input.just_pressed(KeyCode::Right) {
+            0.05
+        } else if keyboard_input.just_pressed(KeyCode::Left) {
+            -0.05
+        } else {
+            0.0
+        };
+        let mut fields = difficulty.fields_mut();
+        let value = &mut fields[cursor.row - levels].1;
+        **value = (**value + step).clamp(0.0, 3.0);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::A) {
+        adaptive.enabled = !adaptive.enabled;
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        next_state.set(AppState::InGame);
+    }
+
+    let Ok(mut text) = q_text.get_single_mut() else {
+        return;
+    };
+    let marker = |row: usize| if row == cursor.row { "> " } else { "  " };
+    let mut lines = vec!["DIFFICULTY".to_string()];
+    for (i, level) in DifficultyLevel::ALL.iter().enumerate() {
+        lines.push(format!("{}{} {}", marker(i), i + 1, level.name()));
+    }
+    if difficulty.level == DifficultyLevel::Custom {
+        for (i, (name, value)) in difficulty.fields_mut().into_iter().enumerate() {
+            lines.push(format!("{}    {}: {:.2}", marker(levels + i), name, value));
+        }
+    }
+    let adaptive_status = if adaptive.enabled { "on" } else { "off" };
+    lines.push(format!("\nAdaptive mode (A): {}", adaptive_status));
+    lines.push("\nPress Enter to start".to_string());
+    lines.push("\nHIGH SCORES".to_string());
+    for (i, entry) in high_scores.entries.iter().enumerate() {
+        lines.push(format!(
+            "{:>2}. {:>8}  {}",
+            i + 1,
+            entry.points,
+            entry.difficulty
+        ));
+    }
+    text.sections[0].value = lines.join("\n");
+}
+
+/// Ends the game when the player dies or quits with Escape, and records the score with the difficulty it was earned on,
+/// marking scores earned with the adaptive mode on.
+pub fn game_over_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    q_player: Query<(), With<Player>>,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    adaptive: Res<AdaptiveDifficulty>,
+    mut high_scores: ResMut<HighScores>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if q_player.is_empty() || keyboard_input.just_pressed(KeyCode::Escape) {
+        let mut name = difficulty.level.name().to_string();
+        if adaptive.enabled {
+            name += "+adaptive";
+        }
+        high_scores.record(score.points, &name);
+        high_scores.save();
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+/// Resets everything a game keeps track of, so that each game starts from the top of the level script.
+pub fn reset_game_system(
+    mut script_line: ResMut<CurrentScriptLine>,
+    mut script_timer: ResMut<ScriptTimer>,
+    mut score: ResMut<Score>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+) {
+    script_line.line_num = 0;
+    *adaptive = AdaptiveDifficulty {
+        enabled: adaptive.enabled,
+        ..default()
+    };
+    script_timer.delay = Timer::from_seconds(0.0, TimerMode::Once);
+    *score = Score::default();
+    *hit_stop = HitStop::default();
+    virtual_time.unpause();
+}
+
+// Everything a game leaves behind: anything placed in the world, squads, and sounds still playing out.
+type GameEntityFilter = (
+    Or<(
+        With<Transform>,
+        With<Squad>,
+        With<SelfDestruct>,
+        With<BossMusic>,
+    )>,
+    Without<Camera>,
+);
+
+/// Clears the arena when a game ends. Only the camera is kept.
+pub fn cleanup_game_system(mut commands: Commands, q_game: Query<Entity, GameEntityFilter>) {
+    for entity in q_game.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,6 +1,13 @@
 use crate::components::*;
 use crate::events::*;
+use crate::geometry::*;
+use crate::ship_parts::load_cluster_bomblet;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use std::f32::consts::PI;
+
+// Collisions that deal at least this much damage to each object shake the camera.
+const HEAVY_COLLISION_SHAKE_THRESHOLD: f32 = 50.0;
 
 pub fn movement_system(
     mut velocity_query: Query<
@@ -33,23 +40,38 @@ pub fn movement_system(
 pub fn move_projectiles_system(
     mut commands: Commands,
     mut projectile_query: Query<
-        (Entity, &mut Projectile, &mut Transform, &Velocity),
+        (
+            Entity,
+            &mut Projectile,
+            &mut Transform,
+            &Velocity,
+            Option<&Faction>,
+        ),
         With<Projectile>,
     >,
     time: Res<Time>,
+    mut explosion_writer: EventWriter<ExplosionEvent>,
 ) {
-    for (entity, mut projectile, mut transform, vel) in projectile_query.iter_mut() {
+    for (entity, mut projectile, mut transform, vel, faction) in projectile_query.iter_mut() {
         let move_dir = vel.velocity * MS_TO_PS * time.delta_seconds();
         transform.translation += move_dir;
         projectile.fuel -= 1.0;
         if projectile.fuel <= 0.0 {
+            // Some projectiles go out with a bang rather than just disappearing.
+            if let Some(explosion) = projectile.projectile_type.behavior().expiry_explosion {
+                explosion_writer.send(ExplosionEvent {
+                    position: transform.translation,
+                    explosion,
+                    faction: faction.copied(),
+                });
+            }
             commands.entity(entity).despawn();
         }
     }
 }
 
 /// This system is checks for collisions between entities with the Clipping component and calculates the
-/// physics result of the collision to be sent as Events.
+/// physics result of the collision to be sent as Events. Allies bounce off each other without taking damage.
 pub fn collision_calculation_system(
     mut q_thing: Query<
         (
@@ -59,14 +81,20 @@ pub fn collision_calculation_system(
             &mut Velocity,
             &Mass,
             &mut Clipping,
+            Option<&Faction>,
         ),
         (With<Clipping>),
     >,
     mut damage_writer: EventWriter<DamageEvent>,
     mut collision_writer: EventWriter<CollisionEvent>,
+    mut impulse_writer: EventWriter<CameraImpulseEvent>,
+    arena: Res<Arena>,
+    factions: Res<FactionTable>,
 ) {
-    for (thing1_e, mut thing1_t, thing1_b, mut thing1_v, thing1_m, thing1_p) in q_thing.iter() {
-        for (thing2_e, mut thing2_t, thing2_b, mut thing2_v, thing2_m, mut thing2_p) in
+    for (thing1_e, mut thing1_t, thing1_b, mut thing1_v, thing1_m, thing1_p, thing1_f) in
+        q_thing.iter()
+    {
+        for (thing2_e, mut thing2_t, thing2_b, mut thing2_v, thing2_m, mut thing2_p, thing2_f) in
             q_thing.iter()
         {
             {
@@ -75,7 +103,7 @@ pub fn collision_calculation_system(
                 {
                     continue;
                 }
-                let distance = thing1_t.translation.distance(thing2_t.translation);
+                let distance = wrapped_distance(thing1_t.translation, thing2_t.translation, &arena);
                 let ship_radius = thing1_b.width_radius;
                 let asteroid_radius = thing2_b.width_radius;
                 if distance < ship_radius + asteroid_radius {
@@ -97,8 +125,9 @@ pub fn collision_calculation_system(
                             + thing2_m.value * thing2_v.velocity.dot(thing2_v.velocity));
 
                     // Get unit vectors indicating the directionality of the collision.
+                    // The two objects may be touching across the edge of the arena.
                     let thing1_line_of_impact =
-                        (thing2_t.translation - thing1_t.translation).normalize();
+                        wrapped_direction(thing1_t.translation, thing2_t.translation, &arena);
                     let thing2_line_of_impact = -thing1_line_of_impact;
 
                     // Project the velocity of each object onto the line of impact.
@@ -176,17 +205,27 @@ pub fn collision_calculation_system(
                     // KE_TO_DMG constant.
                     // We write the kinetic energy absorbed by each object to a DamageEvent, allowing another system
                     // to read them and handle them, factoring in resistances etc. as needed.
-                    if ke_absorbed > 2000.0 {
+                    if ke_absorbed > 2000.0
+                        && factions.can_harm(thing1_f.copied(), thing2_f.copied())
+                    {
+                        let damage_value = KE_TO_DMG * ke_absorbed / 2.0;
                         damage_writer.send(DamageEvent {
                             target: thing1_e,
                             damage_type: DamageType::Kinetic,
-                            damage_value: KE_TO_DMG * ke_absorbed / 2.0,
+                            damage_value,
                         });
                         damage_writer.send(DamageEvent {
                             target: thing2_e,
                             damage_type: DamageType::Kinetic,
-                            damage_value: KE_TO_DMG * ke_absorbed / 2.0,
+                            damage_value,
                         });
+                        // Heavy hits get some impact feel, like explosions do.
+                        if damage_value >= HEAVY_COLLISION_SHAKE_THRESHOLD {
+                            impulse_writer.send(CameraImpulseEvent {
+                                trauma: (damage_value / 200.0).clamp(0.2, 0.6),
+                                hit_stop: 0.03,
+                            });
+                        }
                     }
                 }
             }
@@ -208,67 +247,121 @@ pub fn collision_resolution_system(
     }
 }
 
+// A projectile in flight, and the side that fired it.
+type ProjectileItem<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Projectile,
+    &'a Velocity,
+    Option<&'a Faction>,
+);
+
+/// The events a projectile can cause when it hits something.
+#[derive(SystemParam)]
+pub struct ImpactWriters<'w> {
+    damage: EventWriter<'w, DamageEvent>,
+    explosion: EventWriter<'w, ExplosionEvent>,
+    impulse: EventWriter<'w, ImpulseEvent>,
+}
+
 /// This system checks for collisions between entities that have the Clipping component and those that have the
 /// Phase component, but it does not check for collisions between Clipping/Clipping or Phase/Phase. Sends
-/// damage events if a projectile hits an object.
+/// damage events if a projectile hits an object, or explosion events if the projectile explodes on impact.
+/// Projectiles pass through anything their faction can't harm.
 pub fn check_projectile_collisions(
     mut commands: Commands,
-    mut clipping_query: Query<
-        (Entity, &mut Transform, &CollisionBox),
+    clipping_query: Query<
+        (Entity, &Transform, &CollisionBox, Option<&Faction>),
         (With<Clipping>, Without<Phase>),
     >,
-    mut phase_query: Query<(Entity, &mut Transform, &Projectile), (With<Phase>, Without<Clipping>)>,
+    phase_query: Query<ProjectileItem, (With<Phase>, Without<Clipping>)>,
     asset_server: Res<AssetServer>,
-    mut damage_writer: EventWriter<DamageEvent>,
+    mut impacts: ImpactWriters,
+    arena: Res<Arena>,
+    factions: Res<FactionTable>,
 ) {
-    for (n_e, mut n_t, n_c) in clipping_query.iter_mut() {
-        for (p_e, mut p_t, p_p) in phase_query.iter_mut() {
-            let distance = n_t.translation.distance(p_t.translation);
+    for (p_e, p_t, p_p, p_v, p_f) in phase_query.iter() {
+        for (n_e, n_t, n_c, n_f) in clipping_query.iter() {
+            if !factions.can_harm(p_f.copied(), n_f.copied()) {
+                continue;
+            }
+            let distance = wrapped_distance(n_t.translation, p_t.translation, &arena);
             let n_radius = n_c.width_radius;
             // Replace this with an actual collision box later!
             let p_radius = 20.0 * GLOBAL_RESCALE_C;
             if distance < n_radius + p_radius {
                 commands.entity(p_e).despawn();
 
-                // Lasers have no mass, their damage is based on their base damage value
-                // Shells do have mass, and their damage is their kinetic energy.
-                if p_p.projectile_type == ProjectileType::Laser {
-                    damage_writer.send(DamageEvent {
+                let behavior = p_p.projectile_type.behavior();
+                if let Some(explosion) = behavior.hit_explosion {
+                    // The explosion damages the target along with anything else nearby.
+                    impacts.explosion.send(ExplosionEvent {
+                        position: p_t.translation,
+                        explosion,
+                        faction: p_f.copied(),
+                    });
+                } else {
+                    // Projectiles with mass also deal their kinetic energy as damage. Homing and inherited ship
+                    // velocity mean the projectile's actual speed can differ from its muzzle speed.
+                    let kinetic_damage = if behavior.kinetic {
+                        KE_TO_DMG * 0.5 * p_p.mass * p_v.velocity.length().powf(2.0)
+                    } else {
+                        0.0
+                    };
+                    impacts.damage.send(DamageEvent {
                         target: n_e,
                         damage_type: p_p.damage_type.clone(),
-                        damage_value: p_p.damage_value,
+                        damage_value: p_p.damage_value + kinetic_damage,
                     });
-                } else {
+                    // The target absorbs the projectile's momentum.
+                    if behavior.kinetic {
+                        impacts.impulse.send(ImpulseEvent {
+                            target: n_e,
+                            impulse: p_v.velocity * p_p.mass,
+                        });
+                    }
+                }
+
+                if let Some(sprite_path) = behavior.impact_sprite_path {
                     commands.spawn((
                         SpriteBundle {
-                            transform: p_t.clone().with_scale(GLOBAL_RESCALE_V * 0.5),
-                            texture: asset_server.load("sprites/effects/explosion_tmp.png"),
+                            transform: p_t.with_scale(GLOBAL_RESCALE_V * 0.5),
+                            texture: asset_server.load(sprite_path),
                             ..default()
                         },
                         SelfDestruct {
                             cd_timer: Timer::from_seconds(0.1, TimerMode::Once),
                         },
                     ));
-                    damage_writer.send(DamageEvent {
-                        target: n_e,
-                        damage_type: p_p.damage_type.clone(),
-                        damage_value: p_p.damage_value
-                            + KE_TO_DMG * 0.5 * p_p.mass * p_p.speed.powf(2.0),
-                    });
                 }
+
+                // The projectile is gone, it can't hit anything else.
+                break;
             }
         }
     }
 }
 
 /// This system reads DamageEvent events and handles both the calculations and resolutions involved.
+/// Damage to the player is scaled by the difficulty, and tallied for the adaptive mode. Shielded bosses take none.
 pub fn inflict_damage_system(
     mut damage_reader: EventReader<DamageEvent>,
-    mut health_query: Query<&mut Health>,
+    mut health_query: Query<(&mut Health, Has<Player>, Option<&BossPhases>)>,
+    difficulty: Res<Difficulty>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
 ) {
     for ev in damage_reader.read() {
-        if let Ok(mut target_health) = health_query.get_mut(ev.target) {
-            let total_damage = ev.damage_value;
+        if let Ok((mut target_health, is_player, boss)) = health_query.get_mut(ev.target) {
+            if boss.is_some_and(BossPhases::shielded) {
+                continue;
+            }
+            let total_damage = if is_player {
+                let damage = ev.damage_value * difficulty.incoming_damage;
+                adaptive.damage_taken += damage;
+                damage
+            } else {
+                ev.damage_value
+            };
             println! {"Handled {} dmg.", {total_damage}};
             target_health.value -= total_damage;
         }
@@ -276,20 +369,24 @@ pub fn inflict_damage_system(
 }
 
 /// This system checks specifically for missile collisions with objects that have clipping.
-/// When a missile hits any entity that has clipping, it detonates.
+/// When a missile hits any entity with clipping that its faction can harm, it detonates.
 pub fn check_missile_collisions_system(
-    q_missile: Query<(Entity, &CollisionBox, &Transform), With<Missile>>,
-    q_clipping: Query<
-        (&CollisionBox, &Transform),
-        (Without<Missile>, Without<MineLayerAI>, Without<BossAI>),
-    >,
+    q_missile: Query<(Entity, &CollisionBox, &Transform, Option<&Faction>), With<Missile>>,
+    q_clipping: Query<(&CollisionBox, &Transform, Option<&Faction>), Without<Missile>>,
     mut detonation_event_writer: EventWriter<MissileDetonationEvent>,
+    arena: Res<Arena>,
+    factions: Res<FactionTable>,
 ) {
-    for (missile_entity, missile_box, missile_transform) in q_missile.iter() {
-        for (clip_box, clip_transform) in q_clipping.iter() {
-            let distance = clip_transform
-                .translation
-                .distance(missile_transform.translation);
+    for (missile_entity, missile_box, missile_transform, missile_faction) in q_missile.iter() {
+        for (clip_box, clip_transform, clip_faction) in q_clipping.iter() {
+            if !factions.can_harm(missile_faction.copied(), clip_faction.copied()) {
+                continue;
+            }
+            let distance = wrapped_distance(
+                clip_transform.translation,
+                missile_transform.translation,
+                &arena,
+            );
             let n_radius = clip_box.width_radius;
             // Replace this with an actual collision box later!
             let p_radius = missile_box.width_radius;
@@ -303,52 +400,418 @@ pub fn check_missile_collisions_system(
     }
 }
 
+/// This system arms mines once their arming timers run out, and detonates armed mines when a hostile ship comes within
+/// their trigger radius.
+pub fn mine_trigger_system(
+    mut q_mine: Query<(Entity, &Transform, &mut Mine, Option<&Faction>)>,
+    q_ship: Query<(&Transform, &CollisionBox, &EntityType, Option<&Faction>)>,
+    mut detonation_writer: EventWriter<MissileDetonationEvent>,
+    arena: Res<Arena>,
+    factions: Res<FactionTable>,
+    time: Res<Time>,
+) {
+    for (mine_entity, mine_transform, mut mine, mine_faction) in q_mine.iter_mut() {
+        mine.arming_timer.tick(time.delta());
+        if !mine.arming_timer.finished() {
+            continue;
+        }
+        let triggered = q_ship
+            .iter()
+            .any(|(ship_transform, ship_box, et, ship_faction)| {
+                *et == EntityType::Ship
+                    && factions.hostile(mine_faction.copied(), ship_faction.copied())
+                    && wrapped_distance(
+                        mine_transform.translation,
+                        ship_transform.translation,
+                        &arena,
+                    ) < mine.trigger_radius + ship_box.width_radius
+            });
+        if triggered {
+            detonation_writer.send(MissileDetonationEvent {
+                entity: mine_entity,
+            });
+        }
+    }
+}
+
+/// The events a detonating missile or mine can cause.
+#[derive(SystemParam)]
+pub struct DetonationWriters<'w> {
+    explosion: EventWriter<'w, ExplosionEvent>,
+    mine: EventWriter<'w, SpawnMineEvent>,
+}
+
+/// Where everything is, who can hurt whom, and how hard enemies hit.
+#[derive(SystemParam)]
+pub struct Battlefield<'w> {
+    arena: Res<'w, Arena>,
+    factions: Res<'w, FactionTable>,
+    difficulty: Res<'w, Difficulty>,
+}
+
+// Ordnance, and the side it was fired or laid by.
+type Ordnance<'a> = (
+    &'a Transform,
+    Option<&'a Missile>,
+    Option<&'a Mine>,
+    Option<&'a Faction>,
+);
+type EmpTarget<'a> = (
+    Entity,
+    &'a Transform,
+    &'a CollisionBox,
+    &'a mut Thruster,
+    Option<&'a Faction>,
+);
+
+/// This system reads MissileDetonationEvent events, despawns the missile or mine, and turns it into an explosion.
+/// Mines may do something else as well, depending on their kind. Whatever they leave behind keeps their faction.
 pub fn handle_denotation_event_system(
     mut commands: Commands,
-    mut q_missile: Query<&Transform, With<Missile>>,
-    q_clip: Query<(Entity, &Transform, &CollisionBox), (With<Clipping>, Without<Missile>)>,
+    q_ordnance: Query<Ordnance>,
+    mut q_ship: Query<EmpTarget, Without<Missile>>,
     mut detonation_reader: EventReader<MissileDetonationEvent>,
-    mut damage_writer: EventWriter<DamageEvent>,
+    mut writers: DetonationWriters,
     asset_server: Res<AssetServer>,
+    battlefield: Battlefield,
 ) {
+    let (arena, factions) = (&battlefield.arena, &battlefield.factions);
+    // Something may be set off more than once in the same frame, but it only goes off once.
+    let mut detonated = Vec::new();
     for ev in detonation_reader.read() {
-        if let Ok((missile_transform)) = q_missile.get(ev.entity) {
-            commands.spawn((
-                AudioBundle {
-                    source: asset_server.load("sounds/explosionCrunch_003.ogg"),
-                    ..default()
-                },
-                SelfDestruct {
-                    cd_timer: Timer::from_seconds(0.5, TimerMode::Once),
-                },
-            ));
-            commands.spawn((
-                SpriteBundle {
-                    transform: missile_transform.clone(),
-                    texture: asset_server.load("sprites/effects/explosion_tmp.png"),
-                    ..default()
-                },
-                SelfDestruct {
-                    cd_timer: Timer::from_seconds(0.25, TimerMode::Once),
-                },
-            ));
-            for (entity, clip_transform, clip_box) in q_clip.iter() {
-                let distance = clip_transform
-                    .translation
-                    .distance(missile_transform.translation);
-                let n_radius = clip_box.width_radius;
-                // Replace this with an actual collision box later!
-                let explosion_radius = 200.0;
-                if distance < n_radius + explosion_radius {
-                    // The missile collided with something, register the detonation event.
-                    damage_writer.send(DamageEvent {
-                        target: entity,
-                        damage_type: DamageType::Kinetic,
-                        damage_value: 50.0,
+        if detonated.contains(&ev.entity) {
+            continue;
+        }
+        let Ok((transform, missile, mine, faction)) = q_ordnance.get(ev.entity) else {
+            continue;
+        };
+        detonated.push(ev.entity);
+        commands.entity(ev.entity).despawn();
+
+        if let Some(missile) = missile {
+            writers.explosion.send(ExplosionEvent {
+                position: transform.translation,
+                explosion: missile.explosion,
+                faction: faction.copied(),
+            });
+        }
+        let Some(mine) = mine else {
+            continue;
+        };
+        match mine.kind {
+            MineKind::Standard => {}
+            MineKind::Emp => {
+                // Knock out the thrusters of every ship caught in the blast. Ships that are already disabled
+                // stay on their original timer.
+                for (ship_entity, ship_transform, ship_box, mut thruster, ship_faction) in
+                    q_ship.iter_mut()
+                {
+                    let distance =
+                        wrapped_distance(transform.translation, ship_transform.translation, arena);
+                    if distance < mine.explosion.radius + ship_box.width_radius
+                        && thruster.force > 0.0
+                        && factions.can_harm(faction.copied(), ship_faction.copied())
+                    {
+                        commands.entity(ship_entity).try_insert(EmpDisabled {
+                            timer: Timer::from_seconds(3.0, TimerMode::Once),
+                            thruster_force: thruster.force,
+                        });
+                        thruster.force = 0.0;
+                    }
+                }
+            }
+            MineKind::Cluster => {
+                // Scatter bomblets evenly in every direction.
+                for i in 0..6 {
+                    let direction = Quat::from_rotation_z(i as f32 * PI / 3.0) * Vec3::Y;
+                    writers.mine.send(SpawnMineEvent {
+                        transform: Transform::from_translation(transform.translation)
+                            .with_scale(GLOBAL_RESCALE_V * 0.6),
+                        velocity: direction * 0.6,
+                        mine: load_cluster_bomblet(),
+                        faction: faction.copied(),
                     });
                 }
             }
-            commands.entity(ev.entity).despawn();
+            MineKind::Gravity => {
+                // The gravity well explodes when it collapses, so there is no blast yet.
+                let mut well = commands.spawn((
+                    SpriteBundle {
+                        transform: Transform::from_translation(transform.translation)
+                            .with_scale(GLOBAL_RESCALE_V * 3.0),
+                        texture: asset_server.load("sprites/effects/star2.png"),
+                        sprite: Sprite {
+                            color: MineKind::Gravity.color(),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    GravityWell {
+                        radius: 300.0,
+                        strength: 1500.0,
+                        timer: Timer::from_seconds(2.5, TimerMode::Once),
+                        explosion: mine.explosion,
+                    },
+                ));
+                if let Some(faction) = faction {
+                    well.insert(*faction);
+                }
+                continue;
+            }
+        }
+        writers.explosion.send(ExplosionEvent {
+            position: transform.translation,
+            explosion: mine.explosion,
+            faction: faction.copied(),
+        });
+    }
+}
+
+/// This system pulls ships toward the centers of gravity wells, harder the closer they are, and collapses each well
+/// into an explosion when its time is up.
+pub fn gravity_well_system(
+    mut commands: Commands,
+    mut q_well: Query<(Entity, &mut Transform, &mut GravityWell, Option<&Faction>)>,
+    q_ship: Query<(Entity, &Transform, &EntityType), Without<GravityWell>>,
+    mut impulse_writer: EventWriter<ImpulseEvent>,
+    mut explosion_writer: EventWriter<ExplosionEvent>,
+    arena: Res<Arena>,
+    time: Res<Time>,
+) {
+    for (well_entity, mut well_transform, mut well, faction) in q_well.iter_mut() {
+        well.timer.tick(time.delta());
+        if well.timer.finished() {
+            explosion_writer.send(ExplosionEvent {
+                position: well_transform.translation,
+                explosion: well.explosion,
+                faction: faction.copied(),
+            });
+            commands.entity(well_entity).despawn();
+            continue;
+        }
+        well_transform.rotate_z(4.0 * time.delta_seconds());
+
+        for (ship_entity, ship_transform, et) in q_ship.iter() {
+            if *et != EntityType::Ship {
+                continue;
+            }
+            let delta = wrapped_delta(
+                ship_transform.translation,
+                well_transform.translation,
+                &arena,
+            );
+            let distance = delta.truncate().length();
+            if distance < well.radius {
+                impulse_writer.send(ImpulseEvent {
+                    target: ship_entity,
+                    impulse: delta.truncate().normalize_or_zero().extend(0.0)
+                        * well.strength
+                        * (1.0 - distance / well.radius)
+                        * time.delta_seconds(),
+                });
+            }
+        }
+    }
+}
+
+/// This system restores the thrusters of ships disabled by an EMP once the effect wears off.
+pub fn emp_recovery_system(
+    mut commands: Commands,
+    mut q_disabled: Query<(Entity, &mut EmpDisabled, &mut Thruster)>,
+    time: Res<Time>,
+) {
+    for (entity, mut emp, mut thruster) in q_disabled.iter_mut() {
+        emp.timer.tick(time.delta());
+        if emp.timer.finished() {
+            thruster.force = emp.thruster_force;
+            commands.entity(entity).remove::<EmpDisabled>();
+        }
+    }
+}
+
+type BlastTarget<'a> = (
+    Entity,
+    &'a Transform,
+    &'a CollisionBox,
+    Has<Cover>,
+    Option<&'a Faction>,
+);
+// Missiles and mines aren't caught in blasts, so explosions don't chain.
+type BlastFilter = (With<Clipping>, Without<Missile>, Without<Mine>);
+
+/// This system reads ExplosionEvent events, plays the explosion's sound and visuals, and damages and pushes
+/// everything with Clipping within the explosion's radius that its faction can harm. The blast weakens toward its edge,
+/// and blockable blasts don't reach anything hiding behind Cover. Explosions set off by the player's enemies are scaled
+/// by the difficulty's enemy damage, the same as their guns.
+pub fn handle_explosion_event_system(
+    mut commands: Commands,
+    q_clip: Query<BlastTarget, BlastFilter>,
+    mut explosion_reader: EventReader<ExplosionEvent>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut impulse_writer: EventWriter<ImpulseEvent>,
+    asset_server: Res<AssetServer>,
+    battlefield: Battlefield,
+) {
+    let (arena, factions) = (&battlefield.arena, &battlefield.factions);
+    for ev in explosion_reader.read() {
+        let damage = if factions.hostile(ev.faction, Some(Faction::Player)) {
+            ev.explosion.damage * battlefield.difficulty.enemy_damage
+        } else {
+            ev.explosion.damage
+        };
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load("sounds/explosionCrunch_003.ogg"),
+                ..default()
+            },
+            SelfDestruct {
+                cd_timer: Timer::from_seconds(0.5, TimerMode::Once),
+            },
+        ));
+        // The explosion sprite is sized to match the blast. A 200 px blast uses the global scale.
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(ev.position)
+                    .with_scale(GLOBAL_RESCALE_V * ev.explosion.radius / 200.0),
+                texture: asset_server.load("sprites/effects/explosion_tmp.png"),
+                ..default()
+            },
+            SelfDestruct {
+                cd_timer: Timer::from_seconds(0.25, TimerMode::Once),
+            },
+        ));
+        for (entity, clip_transform, clip_box, _, clip_faction) in q_clip.iter() {
+            if !factions.can_harm(ev.faction, clip_faction.copied()) {
+                continue;
+            }
+            let distance = wrapped_distance(clip_transform.translation, ev.position, arena);
+            let n_radius = clip_box.width_radius;
+            if distance >= n_radius + ev.explosion.radius {
+                continue;
+            }
+            let shielded = ev.explosion.blockable
+                && q_clip
+                    .iter()
+                    .filter(|(cover, _, _, has_cover, _)| *has_cover && *cover != entity)
+                    .any(|(_, cover_transform, cover_box, _, _)| {
+                        segment_blocked(
+                            ev.position,
+                            clip_transform.translation,
+                            cover_transform.translation,
+                            cover_box.width_radius,
+                            arena,
+                        )
+                    });
+            if shielded {
+                continue;
+            }
+
+            // Measure from the blast to the nearest edge of the target.
+            let strength = ev
+                .explosion
+                .falloff
+                .scale((distance - n_radius).max(0.0) / ev.explosion.radius);
+            damage_writer.send(DamageEvent {
+                target: entity,
+                damage_type: DamageType::Kinetic,
+                damage_value: damage * strength,
+            });
+            // Push everything away from the blast.
+            impulse_writer.send(ImpulseEvent {
+                target: entity,
+                impulse: wrapped_direction(ev.position, clip_transform.translation, arena)
+                    * ev.explosion.force
+                    * strength,
+            });
+        }
+    }
+}
+
+/// This system updates beam weapons. Active beams build heat, raycast from the ship's nose to the first Clipping
+/// entity in their path that they can harm, and damage it every tick. Beams pass through allies. Inactive beams cool
+/// down. A beam that overheats can't fire again until it has fully cooled.
+pub fn beam_weapon_system(
+    mut q_beam: Query<(Entity, &Transform, &mut BeamWeapon, Option<&Faction>)>,
+    q_clip: Query<(Entity, &Transform, &CollisionBox, Option<&Faction>), With<Clipping>>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    battlefield: Battlefield,
+    time: Res<Time>,
+) {
+    let (arena, factions) = (&battlefield.arena, &battlefield.factions);
+    for (beam_entity, beam_transform, mut beam, beam_faction) in q_beam.iter_mut() {
+        if !beam.active() {
+            beam.heat.cool(time.delta());
+            continue;
+        }
+
+        let heat_gained = beam.heat_rate * time.delta_seconds();
+        beam.heat.add(heat_gained);
+
+        // Sweeping beams swing back and forth across the ship's nose.
+        let mut direction = beam_transform.up();
+        if let Some(sweep) = beam.sweep {
+            beam.sweep_phase += sweep.speed * time.delta_seconds();
+            direction = Quat::from_rotation_z(sweep.amplitude * beam.sweep_phase.sin()) * direction;
+        }
+        // The beam starts from just in front of the ship, scaled with the ship's sprite.
+        let origin = beam_transform.translation + direction * 75.0 * beam_transform.scale.y;
+
+        // Find the nearest Clipping entity whose collision circle the beam passes through.
+        let mut hit: Option<(Entity, f32)> = None;
+        for (clip_entity, clip_transform, clip_box, clip_faction) in q_clip.iter() {
+            if clip_entity == beam_entity
+                || !factions.can_harm(beam_faction.copied(), clip_faction.copied())
+            {
+                continue;
+            }
+            let to_target = wrapped_delta(origin, clip_transform.translation, arena).truncate();
+            let along = to_target.dot(direction.truncate());
+            let radius_sq = clip_box.width_radius.powi(2);
+            let perp_sq = to_target.length_squared() - along.powi(2);
+            if along < 0.0 || perp_sq > radius_sq {
+                continue;
+            }
+            // Distance along the beam to where it first touches the circle.
+            let distance = (along - (radius_sq - perp_sq).sqrt()).max(0.0);
+            if distance <= beam.range && !hit.is_some_and(|(_, d)| d <= distance) {
+                hit = Some((clip_entity, distance));
+            }
+        }
+
+        beam.origin = origin;
+        beam.direction = direction;
+        beam.length = hit.map_or(beam.range, |(_, distance)| distance);
+
+        beam.tick_timer.tick(time.delta());
+        if beam.tick_timer.just_finished() {
+            if let Some((target, _)) = hit {
+                damage_writer.send(DamageEvent {
+                    target,
+                    damage_type: beam.dmg_type.clone(),
+                    damage_value: beam.dps * beam.tick_timer.duration().as_secs_f32(),
+                });
+            }
+        }
+    }
+}
+
+/// Recharges ships' reactors over time.
+pub fn reactor_recharge_system(mut q_reactor: Query<&mut Reactor>, time: Res<Time>) {
+    for mut reactor in q_reactor.iter_mut() {
+        reactor.energy =
+            (reactor.energy + reactor.recharge_rate * time.delta_seconds()).min(reactor.max_energy);
+    }
+}
+
+/// This system reads ImpulseEvent events and changes the velocities of the pushed entities, taking their mass into
+/// account. Entities without mass can't be pushed.
+pub fn apply_impulse_system(
+    mut impulse_reader: EventReader<ImpulseEvent>,
+    mut q_body: Query<(&mut Velocity, &Mass)>,
+) {
+    for ev in impulse_reader.read() {
+        if let Ok((mut vel, mass)) = q_body.get_mut(ev.target) {
+            vel.velocity += ev.impulse * IMPULSE_SCALE / mass.value;
         }
     }
 }
@@ -1,6 +1,7 @@
 use crate::components::*;
+use crate::events::{ImpulseEvent, SpawnGuidedMissileEvent};
+use crate::geometry::*;
 use crate::ship_parts::*;
-use crate::traits::*;
 use bevy::prelude::*;
 use libm::atan2f;
 use std::f32::consts::PI;
@@ -9,10 +10,22 @@ use std::f32::consts::PI;
 pub fn update_player_velocity_system(
     keyboard_input: Res<Input<KeyCode>>,
     mut player_query: Query<(&Ship, &mut Velocity, &mut Transform, &Mass, &Thruster), With<Player>>,
+    mut reactor_query: Query<&mut Reactor, With<Player>>,
     time: Res<Time>,
 ) {
     if let Ok((ship, mut velocity, mut transform, mass, thruster)) = player_query.get_single_mut() {
-        if keyboard_input.pressed(KeyCode::Up) || keyboard_input.pressed(KeyCode::W) {
+        // Thrusters draw from the reactor, if the ship has one, and cut out when it runs dry.
+        let mut reactor = reactor_query.get_single_mut().ok();
+        let thrust_cost = reactor
+            .as_ref()
+            .map_or(0.0, |r| r.thrust_drain * time.delta_seconds());
+        let has_thrust_energy = reactor.as_ref().map_or(true, |r| r.energy >= thrust_cost);
+        if (keyboard_input.pressed(KeyCode::Up) || keyboard_input.pressed(KeyCode::W))
+            && has_thrust_energy
+        {
+            if let Some(reactor) = reactor.as_mut() {
+                reactor.energy -= thrust_cost;
+            }
             let acceleration = transform.up() * thruster.force / mass.value;
             velocity.velocity += acceleration * time.delta_seconds();
             // There should be a global max speed and an individual max speed.
@@ -32,18 +45,58 @@ pub fn update_player_velocity_system(
     }
 }
 
+type PlayerGunner<'a> = (
+    Entity,
+    &'a mut Ship,
+    &'a Transform,
+    &'a Velocity,
+    &'a Faction,
+    Option<&'a mut Reactor>,
+);
+
 /// Reads player input and fires the player ship's weapon systems accordingly.
 pub fn player_weapons_system(
     mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
     mouse_input: Res<Input<MouseButton>>,
-    mut player_query: Query<(&mut Ship, &Transform, &Velocity), With<Player>>,
+    mut player_query: Query<PlayerGunner, With<Player>>,
     asset_server: Res<AssetServer>,
     mouse_coords: Res<WorldCoords>,
+    mut impulse_writer: EventWriter<ImpulseEvent>,
 ) {
-    if let Ok((mut ship, transform, vel)) = player_query.get_single_mut() {
+    if let Ok((entity, mut ship, transform, vel, faction, mut reactor)) =
+        player_query.get_single_mut()
+    {
+        // Energy weapons can only fire if the reactor has enough energy left for the shot.
+        let has_energy = |weapon: &WeaponSystem, reactor: &Option<Mut<Reactor>>| {
+            reactor
+                .as_ref()
+                .map_or(true, |r| r.energy >= weapon.energy_cost())
+        };
+        if keyboard_input.just_pressed(KeyCode::R) {
+            ship.primary_weapon.resource.reload();
+            ship.secondary_weapon.resource.reload();
+            ship.tertiary_weapon.resource.reload();
+        }
+        // Letting go of a trigger cuts short any burst in progress.
+        if !keyboard_input.pressed(KeyCode::Space) {
+            ship.primary_weapon.release_trigger();
+        }
+        if !mouse_input.pressed(MouseButton::Left) {
+            ship.secondary_weapon.release_trigger();
+        }
+        if !mouse_input.pressed(MouseButton::Right) {
+            ship.tertiary_weapon.release_trigger();
+        }
+
         // Fire Primary Weapon
-        if keyboard_input.pressed(KeyCode::Space) && ship.primary_weapon.cd_timer.finished() {
+        if keyboard_input.pressed(KeyCode::Space)
+            && ship.primary_weapon.ready()
+            && has_energy(&ship.primary_weapon, &reactor)
+        {
+            if let Some(reactor) = reactor.as_mut() {
+                reactor.energy -= ship.primary_weapon.energy_cost();
+            }
             // The projectile's transform should originate from the firing ship.
             let mut projectile_transform =
                 Transform::from_xyz(transform.translation.x, transform.translation.y, 0.0)
@@ -52,31 +105,48 @@ pub fn player_weapons_system(
             projectile_transform.translation += transform.up() * 75.0 * GLOBAL_RESCALE_V;
             // Ensure that it is rotated in a way that aligns with the firing ship.
             projectile_transform.rotation = transform.rotation.clone();
-            // Spawn the initial graphic
-            commands.spawn((
-                (SpriteBundle {
-                    transform: projectile_transform,
-                    texture: asset_server.load(&ship.primary_weapon.on_spawn_sprite_path),
-                    ..default()
-                }),
-                SelfDestruct {
-                    cd_timer: Timer::from_seconds(0.05, TimerMode::Once),
-                },
-            ));
-            commands.spawn((
-                SpriteBundle {
-                    transform: projectile_transform,
-                    texture: asset_server.load(&ship.primary_weapon.sprite_path),
-                    ..default()
-                },
-                // The Projectile is granted value's from the ship's primary_weapon component.
-                // This depends on the type of projectile the cannon fires.
-                ship.primary_weapon
-                    .fire(transform.up(), vel.velocity.length()),
-            ));
+            // The weapon's firing pattern decides how many projectiles this shot is, and where they go.
+            let shots = ship
+                .primary_weapon
+                .fire_pattern(&projectile_transform, vel.velocity.length());
+            // Firing pushes the ship back.
+            impulse_writer.send(ImpulseEvent {
+                target: entity,
+                impulse: recoil(&shots),
+            });
+            for (shot_transform, projectile) in shots {
+                // Spawn the initial graphic
+                commands.spawn((
+                    (SpriteBundle {
+                        transform: shot_transform,
+                        texture: asset_server.load(&ship.primary_weapon.on_spawn_sprite_path),
+                        ..default()
+                    }),
+                    SelfDestruct {
+                        cd_timer: Timer::from_seconds(0.05, TimerMode::Once),
+                    },
+                ));
+                commands.spawn((
+                    SpriteBundle {
+                        transform: shot_transform,
+                        texture: asset_server.load(&ship.primary_weapon.sprite_path),
+                        ..default()
+                    },
+                    // The Projectile is granted value's from the ship's primary_weapon component.
+                    // This depends on the type of projectile the cannon fires.
+                    projectile,
+                    *faction,
+                ));
+            }
         }
         // Fire Secondary Weapon
-        if mouse_input.pressed(MouseButton::Left) && ship.secondary_weapon.cd_timer.finished() {
+        if mouse_input.pressed(MouseButton::Left)
+            && ship.secondary_weapon.ready()
+            && has_energy(&ship.secondary_weapon, &reactor)
+        {
+            if let Some(reactor) = reactor.as_mut() {
+                reactor.energy -= ship.secondary_weapon.energy_cost();
+            }
             let mut projectile_transform =
                 Transform::from_xyz(transform.translation.x, transform.translation.y, 0.0)
                     .with_scale(GLOBAL_RESCALE_V);
@@ -91,32 +161,49 @@ pub fn player_weapons_system(
                     cd_timer: Timer::from_seconds(2.5, TimerMode::Once),
                 },
             ));
-            // Spawn the initial graphic
-            commands.spawn((
-                (SpriteBundle {
-                    transform: projectile_transform,
-                    texture: asset_server.load(&ship.secondary_weapon.on_spawn_sprite_path),
-                    ..default()
-                }),
-                SelfDestruct {
-                    cd_timer: Timer::from_seconds(0.05, TimerMode::Once),
-                },
-            ));
-            commands.spawn((
-                SpriteBundle {
-                    transform: projectile_transform,
-                    texture: asset_server.load(&ship.secondary_weapon.sprite_path),
-                    ..default()
-                },
-                // The Projectile is granted value's from the ship's secondary_weapon component.
-                // This depends on the type of projectile the cannon fires.
-                ship.secondary_weapon
-                    .fire(transform.up(), vel.velocity.length()),
-            ));
+            // The weapon's firing pattern decides how many projectiles this shot is, and where they go.
+            let shots = ship
+                .secondary_weapon
+                .fire_pattern(&projectile_transform, vel.velocity.length());
+            // Firing pushes the ship back.
+            impulse_writer.send(ImpulseEvent {
+                target: entity,
+                impulse: recoil(&shots),
+            });
+            for (shot_transform, projectile) in shots {
+                // Spawn the initial graphic
+                commands.spawn((
+                    (SpriteBundle {
+                        transform: shot_transform,
+                        texture: asset_server.load(&ship.secondary_weapon.on_spawn_sprite_path),
+                        ..default()
+                    }),
+                    SelfDestruct {
+                        cd_timer: Timer::from_seconds(0.05, TimerMode::Once),
+                    },
+                ));
+                commands.spawn((
+                    SpriteBundle {
+                        transform: shot_transform,
+                        texture: asset_server.load(&ship.secondary_weapon.sprite_path),
+                        ..default()
+                    },
+                    // The Projectile is granted value's from the ship's secondary_weapon component.
+                    // This depends on the type of projectile the cannon fires.
+                    projectile,
+                    *faction,
+                ));
+            }
         }
 
         // Fire Tertiary Weapon
-        if mouse_input.pressed(MouseButton::Right) && ship.tertiary_weapon.cd_timer.finished() {
+        if mouse_input.pressed(MouseButton::Right)
+            && ship.tertiary_weapon.ready()
+            && has_energy(&ship.tertiary_weapon, &reactor)
+        {
+            if let Some(reactor) = reactor.as_mut() {
+                reactor.energy -= ship.tertiary_weapon.energy_cost();
+            }
             let position = mouse_coords.coords;
             let y = position.y - transform.translation.y;
             let x = position.x - transform.translation.x;
@@ -136,28 +223,39 @@ pub fn player_weapons_system(
                     cd_timer: Timer::from_seconds(2.5, TimerMode::Once),
                 },
             ));
-            // Spawn the initial graphic
-            commands.spawn((
-                (SpriteBundle {
-                    transform: projectile_transform,
-                    texture: asset_server.load(&ship.tertiary_weapon.on_spawn_sprite_path),
-                    ..default()
-                }),
-                SelfDestruct {
-                    cd_timer: Timer::from_seconds(0.05, TimerMode::Once),
-                },
-            ));
-            commands.spawn((
-                SpriteBundle {
-                    transform: projectile_transform,
-                    texture: asset_server.load(&ship.tertiary_weapon.sprite_path),
-                    ..default()
-                },
-                // The Projectile is granted value's from the ship's tertiary_weapon component.
-                // This depends on the type of projectile the cannon fires.
-                ship.tertiary_weapon
-                    .fire(projectile_transform.up(), vel.velocity.length()),
-            ));
+            // The weapon's firing pattern decides how many projectiles this shot is, and where they go.
+            let shots = ship
+                .tertiary_weapon
+                .fire_pattern(&projectile_transform, vel.velocity.length());
+            // Firing pushes the ship back.
+            impulse_writer.send(ImpulseEvent {
+                target: entity,
+                impulse: recoil(&shots),
+            });
+            for (shot_transform, projectile) in shots {
+                // Spawn the initial graphic
+                commands.spawn((
+                    (SpriteBundle {
+                        transform: shot_transform,
+                        texture: asset_server.load(&ship.tertiary_weapon.on_spawn_sprite_path),
+                        ..default()
+                    }),
+                    SelfDestruct {
+                        cd_timer: Timer::from_seconds(0.05, TimerMode::Once),
+                    },
+                ));
+                commands.spawn((
+                    SpriteBundle {
+                        transform: shot_transform,
+                        texture: asset_server.load(&ship.tertiary_weapon.sprite_path),
+                        ..default()
+                    },
+                    // The Projectile is granted value's from the ship's tertiary_weapon component.
+                    // This depends on the type of projectile the cannon fires.
+                    projectile,
+                    *faction,
+                ));
+            }
         }
     }
 }
@@ -171,4 +269,179 @@ pub fn test_weapon_toggle(
             player_ship.primary_weapon = load_test_torpedo();
         }
     }
+    if keyboard_input.pressed(KeyCode::Y) {
+        if let Ok(mut player_ship) = player_query.get_single_mut() {
+            player_ship.primary_weapon = load_micro_missile();
+        }
+    }
+}
+
+/// While the lock key (Left Shift) is held, locks onto the enemy nearest the mouse cursor. The enemy must stay the
+/// nearest for the lock timer's duration before the lock is made. The lock is kept after the key is released, until
+/// the target is destroyed or a new lock is made.
+pub fn player_target_lock_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut player_query: Query<&mut TargetLock, With<Player>>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    mouse_coords: Res<WorldCoords>,
+    arena: Res<Arena>,
+    time: Res<Time>,
+) {
+    let Ok(mut lock) = player_query.get_single_mut() else {
+        return;
+    };
+
+    // Drop the lock if the target is gone.
+    if let Some(target) = lock.target {
+        if !enemy_query.contains(target) {
+            lock.target = None;
+        }
+    }
+
+    if !keyboard_input.pressed(KeyCode::ShiftLeft) {
+        lock.candidate = None;
+        return;
+    }
+
+    let cursor = mouse_coords.coords.extend(0.0);
+    let nearest = enemy_query
+        .iter()
+        .map(|(entity, transform)| {
+            (
+                entity,
+                wrapped_distance(cursor, transform.translation, &arena),
+            )
+        })
+        .filter(|(_, distance)| *distance < lock.range)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity);
+
+    if nearest != lock.candidate {
+        // Started aiming at something new, restart the lock.
+        lock.candidate = nearest;
+        lock.lock_timer.reset();
+    } else if nearest.is_some() && nearest != lock.target {
+        lock.lock_timer.tick(time.delta());
+        if lock.lock_timer.finished() {
+            lock.target = nearest;
+        }
+    }
+}
+
+/// Launches a guided missile at the player's locked target when F is pressed.
+pub fn player_missile_launch_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut player_query: Query<
+        (&Transform, &TargetLock, &mut MissileLauncher, &Faction),
+        With<Player>,
+    >,
+    mut missile_writer: EventWriter<SpawnGuidedMissileEvent>,
+) {
+    let Ok((transform, lock, mut launcher, faction)) = player_query.get_single_mut() else {
+        return;
+    };
+    let Some(target) = lock.target else {
+        return;
+    };
+    if keyboard_input.pressed(KeyCode::F) && launcher.cd_timer.finished() {
+        launcher.cd_timer.reset();
+        let mut missile_transform =
+            Transform::from_xyz(transform.translation.x, transform.translation.y, 0.0)
+                .with_scale(GLOBAL_RESCALE_V)
+                .with_rotation(transform.rotation);
+        // Launch it from far enough in front of the ship that it doesn't detonate on the player.
+        missile_transform.translation += transform.up() * 100.0 * GLOBAL_RESCALE_V;
+        missile_writer.send(SpawnGuidedMissileEvent {
+            transform: missile_transform,
+            target: Some(target),
+            seeker: Seeker::Radar,
+            faction: Some(*faction),
+        });
+    }
+}
+
+/// Launches a decoy from the player's countermeasure slot when C is pressed, and swaps between flares and chaff
+/// when X is pressed.
+pub fn player_countermeasure_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut player_query: Query<(&Transform, &Velocity, &mut CountermeasureLauncher), With<Player>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok((transform, vel, mut launcher)) = player_query.get_single_mut() else {
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::X) {
+        launcher.kind = match launcher.kind {
+            DecoyKind::Flare => DecoyKind::Chaff,
+            DecoyKind::Chaff => DecoyKind::Flare,
+        };
+    }
+    if keyboard_input.pressed(KeyCode::C) && launcher.cd_timer.finished() && launcher.charges > 0 {
+        launcher.cd_timer.reset();
+        launcher.charges -= 1;
+        let (sprite_path, color) = match launcher.kind {
+            DecoyKind::Flare => ("sprites/effects/star1.png", Color::rgb(1.0, 0.6, 0.2)),
+            DecoyKind::Chaff => ("sprites/effects/star2.png", Color::rgb(0.7, 0.7, 0.8)),
+        };
+        // Drop the decoy behind the ship, drifting away from it.
+        let mut decoy_transform =
+            Transform::from_xyz(transform.translation.x, transform.translation.y, 0.0)
+                .with_scale(GLOBAL_RESCALE_V * 2.0);
+        decoy_transform.translation += transform.down() * 75.0 * GLOBAL_RESCALE_V;
+        commands.spawn((
+            SpriteBundle {
+                transform: decoy_transform,
+                texture: asset_server.load(sprite_path),
+                sprite: Sprite { color, ..default() },
+                ..default()
+            },
+            Decoy {
+                kind: launcher.kind,
+                radius: 300.0,
+                checked: Vec::new(),
+            },
+            Velocity {
+                velocity: vel.velocity * 0.5 + transform.down() * 0.5,
+            },
+            Drag {
+                dampening_factor: 0.98,
+            },
+            SelfDestruct {
+                cd_timer: Timer::from_seconds(3.0, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+/// Collects any pickup the player flies over.
+pub fn player_pickup_system(
+    mut commands: Commands,
+    mut player_query: Query<(&Transform, &mut Health, &mut CountermeasureLauncher), With<Player>>,
+    pickup_query: Query<(Entity, &Transform, &Pickup)>,
+    arena: Res<Arena>,
+) {
+    let Ok((transform, mut health, mut launcher)) = player_query.get_single_mut() else {
+        return;
+    };
+    for (entity, pickup_transform, pickup) in pickup_query.iter() {
+        if wrapped_distance(transform.translation, pickup_transform.translation, &arena) > 40.0 {
+            continue;
+        }
+        match pickup.kind {
+            PickupKind::Repair => health.value = (health.value + 100.0).min(PLAYER_HEALTH),
+            PickupKind::Countermeasures => launcher.charges = launcher.max_charges,
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Fires the player's beam weapon for as long as E is held.
+pub fn player_beam_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut player_query: Query<&mut BeamWeapon, With<Player>>,
+) {
+    if let Ok(mut beam) = player_query.get_single_mut() {
+        beam.firing = keyboard_input.pressed(KeyCode::E);
+    }
 }
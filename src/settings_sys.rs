@@ -0,0 +1,47 @@
+use crate::components::*;
+use bevy::prelude::*;
+
+/// Reads the settings hotkeys and flips the matching options.
+/// F1: Floating damage numbers
+/// F2: Hit flash
+/// F3: Minimap
+/// F4: Off-screen indicators
+/// F5: Cycle screen shake intensity (full, half, off)
+/// F6: Hit-stop
+/// F7: AI state labels
+/// F8: Adaptive difficulty overlay
+pub fn settings_hotkeys_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<Settings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F1) {
+        settings.damage_numbers = !settings.damage_numbers;
+    }
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        settings.hit_flash = !settings.hit_flash;
+    }
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        settings.minimap = !settings.minimap;
+    }
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        settings.edge_indicators = !settings.edge_indicators;
+    }
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        settings.screen_shake_intensity = if settings.screen_shake_intensity > 0.5 {
+            0.5
+        } else if settings.screen_shake_intensity > 0.0 {
+            0.0
+        } else {
+            1.0
+        };
+    }
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        settings.hit_stop = !settings.hit_stop;
+    }
+    if keyboard_input.just_pressed(KeyCode::F7) {
+        settings.ai_states = !settings.ai_states;
+    }
+    if keyboard_input.just_pressed(KeyCode::F8) {
+        settings.adaptive_overlay = !settings.adaptive_overlay;
+    }
+}
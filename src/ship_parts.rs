@@ -23,6 +23,8 @@ pub fn load_basic_torpedo() -> WeaponSystem {
         sound_path: "".to_string(),
         cooldown: 0.5,
         cd_timer: Timer::from_seconds(1.0, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::ammo(8, 3.0),
     }
 }
 
@@ -41,6 +43,28 @@ pub fn load_lunker_torpedo() -> WeaponSystem {
         sound_path: "".to_string(),
         cooldown: 5.0,
         cd_timer: Timer::from_seconds(5.0, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::Unlimited,
+    }
+}
+
+pub fn load_micro_missile() -> WeaponSystem {
+    WeaponSystem {
+        name: "Micro Missile".to_string(),
+        value: 10.0,
+        proj_speed: 200.0, // m/s
+        proj_fuel: 400.0,
+        proj_type: ProjectileType::Missile,
+        proj_mass: 1.0, // kg
+        dmg_type: DamageType::Kinetic,
+        dmg: 0.0,
+        sprite_path: "sprites/projectiles/spaceMissiles_020.png".to_string(),
+        on_spawn_sprite_path: "".to_string(),
+        sound_path: "".to_string(),
+        cooldown: 0.75,
+        cd_timer: Timer::from_seconds(0.75, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::ammo(12, 4.0),
     }
 }
 
@@ -59,6 +83,13 @@ pub fn load_basic_laser() -> WeaponSystem {
         sound_path: "sounds/laserSmall_002.ogg".to_string(),
         cooldown: 0.25,
         cd_timer: Timer::from_seconds(0.2, TimerMode::Once),
+        // Twin wing guns, firing one after the other.
+        pattern: FiringPattern {
+            barrels: vec![Vec2::new(-40.0, -30.0), Vec2::new(40.0, -30.0)],
+            alternate: true,
+            ..default()
+        },
+        resource: WeaponResource::Energy { cost: 4.0 },
     }
 }
 
@@ -77,6 +108,8 @@ pub fn load_basic_cannon() -> WeaponSystem {
         sound_path: "sounds/light_shells.ogg".to_string(),
         cooldown: 0.15,
         cd_timer: Timer::from_seconds(0.1, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::heat(100.0, 3.0, 25.0),
     }
 }
 
@@ -95,6 +128,8 @@ pub fn load_blank_weapon() -> WeaponSystem {
         sound_path: "".to_string(),
         cooldown: 0.0,
         cd_timer: Timer::from_seconds(0.0, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::Unlimited,
     }
 }
 
@@ -113,6 +148,8 @@ pub fn load_test_torpedo() -> WeaponSystem {
         sound_path: "".to_string(),
         cooldown: 0.5,
         cd_timer: Timer::from_seconds(0.5, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::Unlimited,
     }
 }
 
@@ -131,6 +168,8 @@ pub fn load_practice_laser() -> WeaponSystem {
         sound_path: "".to_string(),
         cooldown: 2.0,
         cd_timer: Timer::from_seconds(0.5, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::Unlimited,
     }
 }
 
@@ -149,9 +188,320 @@ pub fn load_drone_laser() -> WeaponSystem {
         sound_path: "".to_string(),
         cooldown: 1.0,
         cd_timer: Timer::from_seconds(0.75, TimerMode::Once),
+        pattern: FiringPattern {
+            inaccuracy: 0.08,
+            ..default()
+        },
+        resource: WeaponResource::Unlimited,
     }
 }
 
+pub fn load_point_defense_laser() -> WeaponSystem {
+    WeaponSystem {
+        name: "Point Defense Laser".to_string(),
+        value: 10.0,
+        proj_speed: 600.0,
+        proj_fuel: 60.0,
+        proj_type: ProjectileType::Laser,
+        proj_mass: 0.0,
+        dmg_type: DamageType::Radiant,
+        dmg: 15.0,
+        sprite_path: "sprites/projectiles/laserGreen14.png".to_string(),
+        on_spawn_sprite_path: "".to_string(),
+        sound_path: "".to_string(),
+        cooldown: 0.3,
+        cd_timer: Timer::from_seconds(0.3, TimerMode::Once),
+        pattern: FiringPattern::default(),
+        resource: WeaponResource::Unlimited,
+    }
+}
+
+pub fn load_boss_spray_laser() -> WeaponSystem {
+    WeaponSystem {
+        name: "Boss Spray Laser".to_string(),
+        value: 10.0,
+        proj_speed: 250.0,
+        proj_fuel: 400.0,
+        proj_type: ProjectileType::Laser,
+        proj_mass: 0.0,
+        dmg_type: DamageType::Radiant,
+        dmg: 10.0,
+        sprite_path: "sprites/projectiles/laserRed09.png".to_string(),
+        on_spawn_sprite_path: "".to_string(),
+        sound_path: "".to_string(),
+        cooldown: 5.0,
+        cd_timer: Timer::from_seconds(5.0, TimerMode::Once),
+        // A long burst from two barrels, swinging back and forth in a sine wave.
+        pattern: FiringPattern {
+            barrels: vec![Vec2::new(-60.0, 0.0), Vec2::new(60.0, 0.0)],
+            sweep: Some(PatternSweep {
+                amplitude: 0.7,
+                speed: 0.25,
+            }),
+            ..FiringPattern::burst(40, 0.06)
+        },
+        resource: WeaponResource::Unlimited,
+    }
+}
+
+//-----------
+//-- Mines --
+//-----------
+pub fn load_standard_mine() -> Mine {
+    Mine {
+        kind: MineKind::Standard,
+        arming_timer: Timer::from_seconds(1.5, TimerMode::Once),
+        trigger_radius: 90.0,
+        // Mines are packed with more explosive than missiles, but their blast is tighter.
+        explosion: Explosion {
+            radius: 150.0,
+            damage: 70.0,
+            force: 2000.0,
+            falloff: Falloff::Quadratic,
+            blockable: true,
+        },
+        blink_timer: Timer::from_seconds(0.4, TimerMode::Repeating),
+    }
+}
+
+pub fn load_emp_mine() -> Mine {
+    Mine {
+        kind: MineKind::Emp,
+        arming_timer: Timer::from_seconds(1.5, TimerMode::Once),
+        trigger_radius: 110.0,
+        // The EMP does little damage itself. Its threat is leaving ships adrift.
+        explosion: Explosion {
+            radius: 220.0,
+            damage: 5.0,
+            force: 0.0,
+            falloff: Falloff::Flat,
+            blockable: true,
+        },
+        blink_timer: Timer::from_seconds(0.25, TimerMode::Repeating),
+    }
+}
+
+pub fn load_cluster_mine() -> Mine {
+    Mine {
+        kind: MineKind::Cluster,
+        arming_timer: Timer::from_seconds(1.5, TimerMode::Once),
+        trigger_radius: 120.0,
+        explosion: Explosion {
+            radius: 80.0,
+            damage: 20.0,
+            force: 800.0,
+            falloff: Falloff::Linear,
+            blockable: true,
+        },
+        blink_timer: Timer::from_seconds(0.4, TimerMode::Repeating),
+    }
+}
+
+/// The small mines scattered by a cluster mine. They arm almost immediately.
+pub fn load_cluster_bomblet() -> Mine {
+    Mine {
+        kind: MineKind::Standard,
+        arming_timer: Timer::from_seconds(0.3, TimerMode::Once),
+        trigger_radius: 60.0,
+        explosion: Explosion {
+            radius: 90.0,
+            damage: 25.0,
+            force: 600.0,
+            falloff: Falloff::Linear,
+            blockable: true,
+        },
+        blink_timer: Timer::from_seconds(0.2, TimerMode::Repeating),
+    }
+}
+
+pub fn load_gravity_mine() -> Mine {
+    Mine {
+        kind: MineKind::Gravity,
+        arming_timer: Timer::from_seconds(1.5, TimerMode::Once),
+        trigger_radius: 100.0,
+        // This is the blast when the gravity well collapses, not when the mine is triggered.
+        explosion: Explosion {
+            radius: 180.0,
+            damage: 60.0,
+            force: 1500.0,
+            falloff: Falloff::Quadratic,
+            blockable: false,
+        },
+        blink_timer: Timer::from_seconds(0.6, TimerMode::Repeating),
+    }
+}
+
+//-----------
+//-- Beams --
+//-----------
+pub fn load_basic_beam() -> BeamWeapon {
+    BeamWeapon {
+        name: "Basic Beam".to_string(),
+        dps: 60.0,
+        dmg_type: DamageType::Radiant,
+        range: 500.0,
+        width: 6.0,
+        sprite_path: "sprites/projectiles/laserBlue04.png".to_string(),
+        heat: HeatSink::new(100.0, 25.0),
+        heat_rate: 40.0,
+        firing: false,
+        tick_timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+        sweep: None,
+        sweep_phase: 0.0,
+        origin: Vec3::ZERO,
+        direction: Vec3::Y,
+        length: 0.0,
+    }
+}
+
+pub fn load_boss_beam() -> BeamWeapon {
+    BeamWeapon {
+        name: "Boss Beam".to_string(),
+        dps: 40.0,
+        dmg_type: DamageType::Radiant,
+        range: 1500.0,
+        width: 14.0,
+        sprite_path: "sprites/projectiles/laserRed09.png".to_string(),
+        heat: HeatSink::new(100.0, 20.0),
+        heat_rate: 25.0,
+        firing: false,
+        tick_timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+        sweep: Some(BeamSweep {
+            amplitude: 0.6,
+            speed: 0.8,
+        }),
+        sweep_phase: 0.0,
+        origin: Vec3::ZERO,
+        direction: Vec3::Y,
+        length: 0.0,
+    }
+}
+
+//---------------
+//-- AI States --
+//---------------
+/// Speedy: close to mid-range, then alternate between firing and briefly thrusting away to change position.
+pub fn load_speedy_ai() -> StateMachine {
+    StateMachine::new(
+        vec![
+            AiState::new("approach"),
+            AiState::timed("attack", 2.0),
+            AiState::timed("reposition", 1.0),
+        ],
+        vec![
+            Transition::new(&[], "approach", StateCondition::TargetFartherThan(500.0)),
+            Transition::new(
+                &["approach"],
+                "attack",
+                StateCondition::TargetCloserThan(500.0),
+            ),
+            Transition::new(&["attack"], "reposition", StateCondition::TimerFinished),
+            Transition::new(&["reposition"], "attack", StateCondition::TimerFinished),
+        ],
+    )
+}
+
+/// Rammer: close to short range, line up on the target, boost into it, then wait for the boost to recharge.
+pub fn load_rammer_ai() -> StateMachine {
+    let mut ram = AiState::timed("ram", 0.5);
+    ram.on_exit = Some(end_ram_boost);
+    StateMachine::new(
+        vec![
+            AiState::new("approach"),
+            AiState::new("line_up"),
+            ram,
+            AiState::timed("recharge", 3.0),
+        ],
+        vec![
+            Transition::new(
+                &["line_up", "ram"],
+                "approach",
+                StateCondition::TargetFartherThan(250.0),
+            ),
+            Transition::new(
+                &["approach"],
+                "line_up",
+                StateCondition::TargetCloserThan(250.0),
+            ),
+            Transition::new(&["line_up"], "ram", StateCondition::TargetAhead(0.20)),
+            Transition::new(&["ram"], "recharge", StateCondition::TimerFinished),
+            Transition::new(&["recharge"], "line_up", StateCondition::TimerFinished),
+        ],
+    )
+}
+
+/// The ram boost can push a rammer well past the usual speed limit; it sheds the excess once the boost ends.
+fn end_ram_boost(_transform: &mut Transform, velocity: &mut Velocity) {
+    velocity.velocity = velocity.velocity.clamp_length_max(MAX_SPEED);
+}
+
+/// Picket: keep out of long range and launch a guided missile every few seconds.
+pub fn load_picket_ai() -> StateMachine {
+    // Backing off doesn't reset the countdown to the next launch.
+    let mut wait = AiState::timed("wait", 3.5);
+    wait.resume_timer = true;
+    StateMachine::new(
+        vec![AiState::new("retreat"), wait, AiState::new("launch")],
+        vec![
+            Transition::new(&[], "retreat", StateCondition::TargetCloserThan(600.0)),
+            Transition::new(
+                &["retreat"],
+                "wait",
+                StateCondition::TargetFartherThan(600.0),
+            ),
+            Transition::new(&["wait"], "launch", StateCondition::TimerFinished),
+            Transition::new(&["launch"], "wait", StateCondition::Always),
+        ],
+    )
+}
+
+//-----------------
+//-- Boss Phases --
+//-----------------
+/// The boss: its parts fight while the core is armored, then the exposed core fights on its own, and it gets
+/// desperate near the end.
+pub fn load_boss_phases(parts: Vec<Entity>) -> BossPhases {
+    BossPhases::new(
+        vec![
+            BossPhase {
+                name: "armored",
+                health_threshold: 1.0,
+                patterns: vec![
+                    BossPattern::every(BossAttack::Missiles, 4.0),
+                    BossPattern::every(BossAttack::Rammers, 6.0),
+                ],
+                drift_speed: 0.0,
+                music: Some("sounds/boss_theme.ogg"),
+            },
+            BossPhase {
+                name: "exposed",
+                health_threshold: 1.0,
+                patterns: vec![
+                    BossPattern::continuous(BossAttack::Spray),
+                    BossPattern::continuous(BossAttack::Beam),
+                    BossPattern::every(BossAttack::Drones, 8.0),
+                ],
+                drift_speed: 0.0,
+                music: None,
+            },
+            BossPhase {
+                name: "desperate",
+                health_threshold: 0.4,
+                patterns: vec![
+                    BossPattern::continuous(BossAttack::Spray),
+                    BossPattern::continuous(BossAttack::Beam),
+                    BossPattern::every(BossAttack::Drones, 6.0),
+                    BossPattern::every(BossAttack::Rammers, 4.0),
+                ],
+                drift_speed: 0.3,
+                music: Some("sounds/boss_desperate.ogg"),
+            },
+        ],
+        BOSS_HEALTH,
+        parts,
+    )
+}
+
 //---------------
 //-- Thrusters --
 //---------------
@@ -12,8 +12,8 @@ pub fn load_speedy_ship() -> (
     Health,
     Mass,
     EntityType,
-    AITimer,
-    AITimer2,
+    Accuracy,
+    StateMachine,
     SpeedyAI,
 ) {
     (
@@ -39,12 +39,8 @@ pub fn load_speedy_ship() -> (
         Health { value: 10.0 },
         Mass { value: 80000.0 },
         EntityType::Ship,
-        AITimer {
-            cd_timer: Timer::from_seconds(2.0, TimerMode::Once),
-        },
-        AITimer2 {
-            cd_timer: Timer::from_seconds(1.0, TimerMode::Once),
-        },
+        Accuracy { value: 0.8 },
+        load_speedy_ai(),
         SpeedyAI {},
     )
 }
@@ -59,6 +55,7 @@ pub fn load_drone_ship() -> (
     Health,
     Mass,
     EntityType,
+    Accuracy,
     DroneAI,
 ) {
     (
@@ -84,6 +81,7 @@ pub fn load_drone_ship() -> (
         Health { value: 100.0 },
         Mass { value: 1000.0 },
         EntityType::Ship,
+        Accuracy { value: 0.25 },
         DroneAI {},
     )
 }
@@ -98,8 +96,7 @@ pub fn load_rammer_ship() -> (
     Health,
     Mass,
     EntityType,
-    AITimer,
-    AITimer2,
+    StateMachine,
     RammerAI,
 ) {
     (
@@ -125,12 +122,7 @@ pub fn load_rammer_ship() -> (
         Health { value: 200.0 },
         Mass { value: 200000.0 },
         EntityType::Ship,
-        AITimer {
-            cd_timer: Timer::from_seconds(0.5, TimerMode::Once),
-        },
-        AITimer2 {
-            cd_timer: Timer::from_seconds(3.0, TimerMode::Once),
-        },
+        load_rammer_ai(),
         RammerAI {},
     )
 }
@@ -145,14 +137,13 @@ pub fn load_lunker_ship() -> (
     Health,
     Mass,
     EntityType,
-    AITimer,
-    AITimer2,
-    SpeedyAI,
+    Accuracy,
+    LunkerAI,
 ) {
     (
         Ship {
             turn_speed: f32::to_radians(1.25),
-            primary_weapon: load_basic_torpedo(),
+            primary_weapon: load_lunker_torpedo(),
             secondary_weapon: load_blank_weapon(),
             tertiary_weapon: load_blank_weapon(),
         },
@@ -172,13 +163,53 @@ pub fn load_lunker_ship() -> (
         Health { value: 100.0 },
         Mass { value: 140000.0 },
         EntityType::Ship,
-        AITimer {
-            cd_timer: Timer::from_seconds(2.0, TimerMode::Once),
+        Accuracy { value: 1.0 },
+        LunkerAI {},
+    )
+}
+
+pub fn load_defender_ship() -> (
+    Ship,
+    Enemy,
+    Thruster,
+    Clipping,
+    Drag,
+    CollisionBox,
+    Health,
+    Mass,
+    EntityType,
+    Accuracy,
+    DefenderAI,
+    MissileLauncher,
+) {
+    (
+        Ship {
+            turn_speed: f32::to_radians(1.0),
+            primary_weapon: load_basic_cannon(),
+            secondary_weapon: load_blank_weapon(),
+            tertiary_weapon: load_blank_weapon(),
         },
-        AITimer2 {
-            cd_timer: Timer::from_seconds(1.0, TimerMode::Once),
+        Enemy {},
+        load_basic_thruster(),
+        Clipping {
+            cd_timer: Timer::from_seconds(0.1, TimerMode::Once),
+        },
+        Drag {
+            dampening_factor: 0.995,
+        },
+        CollisionBox {
+            shape: Shape::Circle,
+            width_radius: 48.0 * GLOBAL_RESCALE_C,
+            height: 48.0 * GLOBAL_RESCALE_C,
+        },
+        Health { value: 300.0 },
+        Mass { value: 250000.0 },
+        EntityType::Ship,
+        Accuracy { value: 0.7 },
+        DefenderAI {},
+        MissileLauncher {
+            cd_timer: Timer::from_seconds(4.0, TimerMode::Once),
         },
-        SpeedyAI {},
     )
 }
 
@@ -235,6 +266,7 @@ pub fn load_turret_ship() -> (
     Health,
     Mass,
     EntityType,
+    Accuracy,
     TurretAI,
 ) {
     (
@@ -260,6 +292,7 @@ pub fn load_turret_ship() -> (
         Health { value: 50.0 },
         Mass { value: 100000.0 },
         EntityType::Ship,
+        Accuracy { value: 0.5 },
         TurretAI {},
     )
 }
@@ -274,7 +307,7 @@ pub fn load_picket_ship() -> (
     Health,
     Mass,
     EntityType,
-    AITimer,
+    StateMachine,
     PicketAI,
 ) {
     (
@@ -300,9 +333,7 @@ pub fn load_picket_ship() -> (
         Health { value: 100.0 },
         Mass { value: 80000.0 },
         EntityType::Ship,
-        AITimer {
-            cd_timer: Timer::from_seconds(3.5, TimerMode::Once),
-        },
+        load_picket_ai(),
         PicketAI {},
     )
 }
@@ -316,15 +347,14 @@ pub fn load_boss_ship() -> (
     Health,
     Mass,
     EntityType,
-    AITimer,
-    AITimer2,
     BossAI,
+    BeamWeapon,
 ) {
     (
         Ship {
-            turn_speed: f32::to_radians(1.25),
+            turn_speed: f32::to_radians(0.5),
             primary_weapon: load_basic_torpedo(),
-            secondary_weapon: load_blank_weapon(),
+            secondary_weapon: load_boss_spray_laser(),
             tertiary_weapon: load_blank_weapon(),
         },
         Enemy {},
@@ -339,15 +369,70 @@ pub fn load_boss_ship() -> (
             width_radius: 450.0 * GLOBAL_RESCALE_C,
             height: 450.0 * GLOBAL_RESCALE_C,
         },
-        Health { value: 5000.0 },
+        Health { value: BOSS_HEALTH },
         Mass { value: 100000000.0 },
         EntityType::Ship,
-        AITimer {
-            cd_timer: Timer::from_seconds(1.0, TimerMode::Once),
+        BossAI {},
+        load_boss_beam(),
+    )
+}
+
+/// A turret bolted onto the boss's hull. It aims and fires on its own, like any other turret.
+pub fn load_boss_turret() -> (
+    Ship,
+    Enemy,
+    Thruster,
+    Clipping,
+    Drag,
+    CollisionBox,
+    Health,
+    Mass,
+    EntityType,
+    Accuracy,
+    TurretAI,
+) {
+    (
+        Ship {
+            turn_speed: f32::to_radians(2.0),
+            primary_weapon: load_basic_cannon(),
+            secondary_weapon: load_blank_weapon(),
+            tertiary_weapon: load_blank_weapon(),
         },
-        AITimer2 {
-            cd_timer: Timer::from_seconds(3.0, TimerMode::Once),
+        Enemy {},
+        load_basic_thruster(),
+        Clipping {
+            cd_timer: Timer::from_seconds(0.1, TimerMode::Once),
         },
-        BossAI {},
+        Drag {
+            dampening_factor: 1.0,
+        },
+        CollisionBox {
+            shape: Shape::Circle,
+            width_radius: 38.0 * GLOBAL_RESCALE_C,
+            height: 38.0 * GLOBAL_RESCALE_C,
+        },
+        Health { value: 300.0 },
+        Mass { value: 100000000.0 },
+        EntityType::Ship,
+        Accuracy { value: 0.6 },
+        TurretAI {},
+    )
+}
+
+/// A missile bay on the boss's hull. It has no weapons of its own. The boss launches missiles from it.
+pub fn load_boss_missile_bay() -> (Enemy, Clipping, CollisionBox, Health, Mass, EntityType) {
+    (
+        Enemy {},
+        Clipping {
+            cd_timer: Timer::from_seconds(0.1, TimerMode::Once),
+        },
+        CollisionBox {
+            shape: Shape::Circle,
+            width_radius: 60.0 * GLOBAL_RESCALE_C,
+            height: 60.0 * GLOBAL_RESCALE_C,
+        },
+        Health { value: 400.0 },
+        Mass { value: 100000000.0 },
+        EntityType::Ship,
     )
 }
@@ -1,21 +1,22 @@
 use crate::components::*;
-use crate::events::{SpawnGuidedMissileEvent, SpawnMineEvent};
+use crate::events::{
+    CameraImpulseEvent, MissileDetonationEvent, SpawnGuidedMissileEvent, SpawnMineEvent,
+};
 use crate::ship_parts::*;
 use crate::ships::*;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
 use rand::prelude::*;
 use std::f32::consts::PI;
 
 pub fn spawn_player_system(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
     asset_server: Res<AssetServer>,
 ) {
-    let window: &Window = window_query.get_single().unwrap();
     commands.spawn((
         SpriteBundle {
-            transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0)
+            transform: Transform::from_xyz(arena.width / 2.0, arena.height / 2.0, 0.0)
                 .with_scale(GLOBAL_RESCALE_V),
             texture: asset_server.load("sprites/ships/playerShip1_blue.png"),
             ..default()
@@ -46,23 +47,64 @@ pub fn spawn_player_system(
             width_radius: 38.0 * GLOBAL_RESCALE_C,
             height: 38.0 * GLOBAL_RESCALE_C,
         },
-        Health { value: 500.0 },
+        Health {
+            value: PLAYER_HEALTH,
+        },
         Mass { value: 100000.0 },
         EntityType::Ship,
+        // The player's optional ship modules. Bundles are limited in size, so these are grouped together.
+        (
+            TargetLock {
+                target: None,
+                candidate: None,
+                lock_timer: Timer::from_seconds(0.5, TimerMode::Once),
+                range: 150.0,
+            },
+            MissileLauncher {
+                cd_timer: Timer::from_seconds(1.5, TimerMode::Once),
+            },
+            PointDefense {
+                weapon: load_point_defense_laser(),
+                range: 350.0,
+            },
+            CountermeasureLauncher {
+                kind: DecoyKind::Flare,
+                charges: 6,
+                max_charges: 6,
+                cd_timer: Timer::from_seconds(0.75, TimerMode::Once),
+            },
+            load_basic_beam(),
+            Reactor {
+                energy: 100.0,
+                max_energy: 100.0,
+                recharge_rate: 20.0,
+                thrust_drain: 8.0,
+            },
+            Faction::Player,
+        ),
     ));
 }
 
+/// The data files the level script can build enemies from, and the difficulty that paces its waves.
+#[derive(SystemParam)]
+pub struct Blueprints<'w> {
+    behaviors: Res<'w, BehaviorLibrary>,
+    squads: Res<'w, SquadLibrary>,
+    difficulty: Res<'w, Difficulty>,
+    adaptive: Res<'w, AdaptiveDifficulty>,
+}
+
 pub fn read_script_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     level_script: Res<LevelScript>,
     mut script_line: ResMut<CurrentScriptLine>,
     mut script_timer: ResMut<ScriptTimer>,
-    q_window: Query<&Window, With<PrimaryWindow>>,
+    blueprints: Blueprints,
+    arena: Res<Arena>,
 ) {
     if script_timer.delay.finished() {
         let mut rng = thread_rng();
-        let window = q_window.get_single().unwrap();
         for (i, line) in level_script.txt.as_str().trim().lines().enumerate() {
             // Only want to read from the current line_num onwards.
             if i < script_line.line_num {
@@ -78,201 +120,410 @@ pub fn read_script_system(
             // If the line begins with "delay", we need to pause the script execution for the given number of seconds.
             // Change the delay timer in the ScriptTimer resource to have the correct delay time and start it.
             if line_data[0] == "delay" {
-                let secs = line_data[1].parse::<f32>().unwrap();
+                let secs = line_data[1].parse::<f32>().unwrap()
+                    * blueprints.difficulty.script_delay
+                    * blueprints.adaptive.spawn_delay();
                 script_timer.delay = Timer::from_seconds(secs, TimerMode::Once);
                 script_timer.delay.reset();
                 break;
             } else {
-                let ship_type = line_data[0];
-                let mut x = line_data[1].parse::<f32>().unwrap();
-                let mut y = line_data[2].parse::<f32>().unwrap();
+                // Squad lines name their template after the word "squad", so their coordinates come one field later.
+                let is_squad = line_data[0] == "squad";
+                let ship_type = if is_squad { line_data[1] } else { line_data[0] };
+                let coords = if is_squad {
+                    &line_data[2..]
+                } else {
+                    &line_data[1..]
+                };
+                let mut x = coords[0].parse::<f32>().unwrap();
+                let mut y = coords[1].parse::<f32>().unwrap();
+                // An optional field after the coordinates names the faction the ships fly for. Pirates by default.
+                let faction = coords
+                    .get(2)
+                    .map(|name| {
+                        Faction::from_name(name.trim())
+                            .unwrap_or_else(|| panic!("Unknown faction in level script: {}", name))
+                    })
+                    .unwrap_or(Faction::Pirates);
 
                 // -1.0 of x or y signifies that we want to randomize the coordinates.
                 if x == -1.0 {
-                    x = rng.gen::<f32>() * window.width()
+                    x = rng.gen::<f32>() * arena.width
                 }
                 if y == -1.0 {
-                    y = rng.gen::<f32>() * window.height()
+                    y = rng.gen::<f32>() * arena.height
                 }
 
-                let ship_sprite_path = match ship_type {
-                    "picket" => "sprites/ships/picket.png".to_string(),
-                    "drone" => "sprites/ships/drone.png".to_string(),
-                    "speedy" => "sprites/ships/speedy.png".to_string(),
-                    "lunker" => "sprites/ships/lunker.png".to_string(),
-                    "mine_layer" => "sprites/ships/mine_layer.png".to_string(),
-                    "rammer" => "sprites/ships/rammer.png".to_string(),
-                    "turret" => "sprites/ships/turret.png".to_string(),
-                    "boss" => "sprites/ships/boss.png".to_string(),
-                    _ => "sprites/ships/turret.png".to_string(),
-                };
-
-                match ship_type {
-                    "picket" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_picket_ship(),
-                        ));
-                    }
-                    "drone" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_drone_ship(),
-                        ));
-                    }
-                    "speedy" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_speedy_ship(),
-                        ));
-                    }
-                    "lunker" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V * 1.5),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_lunker_ship(),
-                        ));
-                    }
-                    "mine_layer" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V)
-                                    .with_rotation(Quat::from_rotation_z(random::<f32>() * 2.0)),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_minelayer_ship(),
-                        ));
-                    }
-                    "rammer" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_rammer_ship(),
-                        ));
-                    }
-                    "turret" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_turret_ship(),
-                        ));
-                    }
-                    "boss" => {
-                        commands.spawn((
-                            SpriteBundle {
-                                transform: Transform::from_xyz(x, y, 0.0)
-                                    .with_scale(GLOBAL_RESCALE_V * 4.0)
-                                    .with_rotation(Quat::from_rotation_z(-3.0 * PI / 4.0)),
-                                texture: asset_server.load(ship_sprite_path),
-                                ..default()
-                            },
-                            Velocity {
-                                velocity: Vec3 {
-                                    x: 0.0,
-                                    y: 0.0,
-                                    z: 0.0,
-                                },
-                            },
-                            load_boss_ship(),
-                        ));
-                    }
-                    _ => {
-                        println!("ship_type not recognized!")
-                    }
+                if is_squad {
+                    spawn_squad(
+                        &mut commands,
+                        &asset_server,
+                        &blueprints,
+                        ship_type,
+                        x,
+                        y,
+                        faction,
+                    );
+                } else {
+                    spawn_ship(
+                        &mut commands,
+                        &asset_server,
+                        &blueprints.behaviors,
+                        ship_type,
+                        x,
+                        y,
+                        faction,
+                    );
                 }
             }
         }
     }
 }
 
+/// Spawns every ship of the named squad template, formed up behind a leader at (x, y) and facing up.
+fn spawn_squad(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    blueprints: &Blueprints,
+    name: &str,
+    x: f32,
+    y: f32,
+    faction: Faction,
+) {
+    let Some(slots) = blueprints.squads.squads.get(name) else {
+        println!("squad not recognized!");
+        return;
+    };
+    let squad = commands.spawn_empty().id();
+    let mut members = Vec::new();
+    for slot in slots {
+        let Some(member) = spawn_ship(
+            commands,
+            asset_server,
+            &blueprints.behaviors,
+            &slot.ship_type,
+            x + slot.offset.x,
+            y + slot.offset.y,
+            faction,
+        ) else {
+            continue;
+        };
+        commands.entity(member).insert(SquadMember {
+            squad,
+            offset: slot.offset,
+            flank_angle: slot.flank_angle,
+            goal: None,
+            flanked: false,
+        });
+        members.push(member);
+    }
+    if members.is_empty() {
+        commands.entity(squad).despawn();
+        return;
+    }
+    commands.entity(squad).insert(Squad {
+        name: name.to_string(),
+        leader: members[0],
+        members,
+    });
+}
+
+/// Spawns one enemy ship of the given type, as named in the level script, flying for the given faction.
+/// Returns None for unknown types.
+pub fn spawn_ship(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    behaviors: &BehaviorLibrary,
+    ship_type: &str,
+    x: f32,
+    y: f32,
+    faction: Faction,
+) -> Option<Entity> {
+    let ship_sprite_path = match ship_type {
+        "picket" => "sprites/ships/picket.png".to_string(),
+        "drone" => "sprites/ships/drone.png".to_string(),
+        "speedy" => "sprites/ships/speedy.png".to_string(),
+        "lunker" => "sprites/ships/lunker.png".to_string(),
+        // Defenders don't have their own art yet, so they fly a scaled-up drone hull.
+        "defender" => "sprites/ships/drone.png".to_string(),
+        "mine_layer" => "sprites/ships/mine_layer.png".to_string(),
+        "rammer" => "sprites/ships/rammer.png".to_string(),
+        "turret" => "sprites/ships/turret.png".to_string(),
+        "boss" => "sprites/ships/boss.png".to_string(),
+        _ => "sprites/ships/turret.png".to_string(),
+    };
+
+    let entity = match ship_type {
+        "picket" => {
+            commands
+                .spawn((
+                    SpriteBundle {
+                        transform: Transform::from_xyz(x, y, 0.0).with_scale(GLOBAL_RESCALE_V),
+                        texture: asset_server.load(ship_sprite_path),
+                        ..default()
+                    },
+                    Velocity {
+                        velocity: Vec3 {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                    },
+                    load_picket_ship(),
+                    // Pickets keep their distance from whoever is coming for them.
+                    TargetPolicy::MostThreatening,
+                ))
+                .id()
+        }
+        "drone" => commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(x, y, 0.0).with_scale(GLOBAL_RESCALE_V),
+                    texture: asset_server.load(ship_sprite_path),
+                    ..default()
+                },
+                Velocity {
+                    velocity: Vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                },
+                load_drone_ship(),
+            ))
+            .id(),
+        "speedy" => {
+            commands
+                .spawn((
+                    SpriteBundle {
+                        transform: Transform::from_xyz(x, y, 0.0).with_scale(GLOBAL_RESCALE_V),
+                        texture: asset_server.load(ship_sprite_path),
+                        ..default()
+                    },
+                    Velocity {
+                        velocity: Vec3 {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                    },
+                    load_speedy_ship(),
+                    // Speedies pick off the weakest ship they can find.
+                    TargetPolicy::LowestHealth,
+                ))
+                .id()
+        }
+        "lunker" => commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(x, y, 0.0).with_scale(GLOBAL_RESCALE_V * 1.5),
+                    texture: asset_server.load(ship_sprite_path),
+                    ..default()
+                },
+                Velocity {
+                    velocity: Vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                },
+                load_lunker_ship(),
+                behaviors.get("lunker"),
+            ))
+            .id(),
+        "defender" => commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(x, y, 0.0).with_scale(GLOBAL_RESCALE_V * 1.25),
+                    texture: asset_server.load(ship_sprite_path),
+                    ..default()
+                },
+                Velocity {
+                    velocity: Vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                },
+                load_defender_ship(),
+                behaviors.get("defender"),
+            ))
+            .id(),
+        "mine_layer" => commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(x, y, 0.0)
+                        .with_scale(GLOBAL_RESCALE_V)
+                        .with_rotation(Quat::from_rotation_z(random::<f32>() * 2.0)),
+                    texture: asset_server.load(ship_sprite_path),
+                    ..default()
+                },
+                Velocity {
+                    velocity: Vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                },
+                load_minelayer_ship(),
+            ))
+            .id(),
+        "rammer" => commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(x, y, 0.0).with_scale(GLOBAL_RESCALE_V),
+                    texture: asset_server.load(ship_sprite_path),
+                    ..default()
+                },
+                Velocity {
+                    velocity: Vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                },
+                load_rammer_ship(),
+            ))
+            .id(),
+        "turret" => commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(x, y, 0.0).with_scale(GLOBAL_RESCALE_V),
+                    texture: asset_server.load(ship_sprite_path),
+                    ..default()
+                },
+                Velocity {
+                    velocity: Vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                },
+                load_turret_ship(),
+            ))
+            .id(),
+        "boss" => {
+            let boss = commands
+                .spawn((
+                    SpriteBundle {
+                        transform: Transform::from_xyz(x, y, 0.0)
+                            .with_scale(GLOBAL_RESCALE_V * 4.0)
+                            .with_rotation(Quat::from_rotation_z(-3.0 * PI / 4.0)),
+                        texture: asset_server.load(ship_sprite_path),
+                        ..default()
+                    },
+                    Velocity {
+                        velocity: Vec3 {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                    },
+                    load_boss_ship(),
+                    TargetPolicy::MostThreatening,
+                    // The boss is big enough to shelter ships from explosions on its far side.
+                    Cover {},
+                ))
+                .id();
+            let parts =
+                spawn_boss_parts(commands, asset_server, boss, Vec3::new(x, y, 0.0), faction);
+            let phases = load_boss_phases(parts);
+            // Later phases switch tracks through their BossPhaseEvents.
+            if let Some(track) = phases.phase().music {
+                commands.spawn((
+                    AudioBundle {
+                        source: asset_server.load(track),
+                        settings: PlaybackSettings::LOOP,
+                    },
+                    BossMusic {},
+                ));
+            }
+            commands.entity(boss).insert(phases);
+            boss
+        }
+        _ => {
+            println!("ship_type not recognized!");
+            return None;
+        }
+    };
+    commands.entity(entity).insert(faction);
+    Some(entity)
+}
+
+// Where the boss's parts sit around its hull, as angles in radians from the boss's right.
+const BOSS_TURRET_ANGLES: [f32; 3] = [0.0, 2.2, 4.0];
+const BOSS_MISSILE_BAY_ANGLES: [f32; 2] = [1.1, 5.2];
+const BOSS_PART_DISTANCE: f32 = 190.0;
+
+/// Spawns the turrets and missile bays that shield the boss's core. Returns them so the boss can keep track of them.
+fn spawn_boss_parts(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    boss: Entity,
+    position: Vec3,
+    faction: Faction,
+) -> Vec<Entity> {
+    let mut parts = Vec::new();
+    let placements = BOSS_TURRET_ANGLES
+        .iter()
+        .map(|angle| (BossPartKind::Turret, *angle))
+        .chain(
+            BOSS_MISSILE_BAY_ANGLES
+                .iter()
+                .map(|angle| (BossPartKind::MissileBay, *angle)),
+        );
+    for (kind, angle) in placements {
+        let offset = Vec2::from_angle(angle) * BOSS_PART_DISTANCE;
+        let part = BossPart { boss, kind, offset };
+        let transform = Transform::from_translation(position + offset.extend(0.0))
+            .with_scale(GLOBAL_RESCALE_V)
+            .with_rotation(Quat::from_rotation_arc(
+                Vec3::Y,
+                offset.normalize().extend(0.0),
+            ));
+        let velocity = Velocity {
+            velocity: Vec3::ZERO,
+        };
+        let entity = match kind {
+            BossPartKind::Turret => commands.spawn((
+                SpriteBundle {
+                    transform,
+                    texture: asset_server.load("sprites/ships/turret.png"),
+                    ..default()
+                },
+                velocity,
+                load_boss_turret(),
+                part,
+            )),
+            BossPartKind::MissileBay => commands.spawn((
+                SpriteBundle {
+                    transform,
+                    texture: asset_server.load("sprites/ships/mine_layer.png"),
+                    ..default()
+                },
+                velocity,
+                load_boss_missile_bay(),
+                part,
+            )),
+        }
+        .insert(faction)
+        .id();
+        parts.push(entity);
+    }
+    parts
+}
+
 pub fn spawn_asteroid_system(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
     asset_server: Res<AssetServer>,
 ) {
-    let window = window_query.get_single().unwrap();
     let mut rng = thread_rng();
-    for _ in 0..10 {
-        let random_x = rng.gen::<f32>() * window.width();
-        let random_y = rng.gen::<f32>() * window.height();
+    // Keep roughly the same density of asteroids as a 1600x1000 arena with 10 asteroids.
+    let count = (10.0 * arena.width * arena.height / (1600.0 * 1000.0)) as usize;
+    for _ in 0..count {
+        let random_x = rng.gen::<f32>() * arena.width;
+        let random_y = rng.gen::<f32>() * arena.height;
         let asteroid_rescaler = rng.gen::<f32>();
 
         commands.spawn((
@@ -283,6 +534,7 @@ pub fn spawn_asteroid_system(
                 ..default()
             },
             Asteroid {},
+            Cover {},
             Clipping {
                 cd_timer: Timer::from_seconds(0.15, TimerMode::Once),
             },
@@ -307,14 +559,114 @@ pub fn spawn_asteroid_system(
     }
 }
 
+type ScaledByDifficulty<'a> = (
+    &'a mut Health,
+    Option<&'a mut Ship>,
+    Option<&'a mut BeamWeapon>,
+    Option<&'a mut Accuracy>,
+    Option<&'a mut BossPhases>,
+);
+
+/// Scales newly spawned enemies to the difficulty: their health, the damage of their weapons, and how well they aim.
+/// How well they aim is also nudged by the adaptive modifier at the time they spawn. Missiles, mines and other
+/// explosives are scaled when they go off instead.
+pub fn apply_difficulty_system(
+    mut q_enemy: Query<ScaledByDifficulty, Added<Enemy>>,
+    difficulty: Res<Difficulty>,
+    adaptive: Res<AdaptiveDifficulty>,
+) {
+    for (mut health, ship, beam, accuracy, boss) in q_enemy.iter_mut() {
+        health.value *= difficulty.enemy_health;
+        // Bosses judge their phases by how much of their health is left, so they need to know their new maximum.
+        if let Some(mut boss) = boss {
+            boss.max_health *= difficulty.enemy_health;
+        }
+        if let Some(mut ship) = ship {
+            ship.primary_weapon.dmg *= difficulty.enemy_damage;
+            ship.secondary_weapon.dmg *= difficulty.enemy_damage;
+            ship.tertiary_weapon.dmg *= difficulty.enemy_damage;
+        }
+        if let Some(mut beam) = beam {
+            beam.dps *= difficulty.enemy_damage;
+        }
+        if let Some(mut accuracy) = accuracy {
+            accuracy.value =
+                (accuracy.value * difficulty.enemy_accuracy * adaptive.accuracy()).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// When the adaptive mode is on, watches for the player coming close to death, and every few seconds nudges the
+/// adaptive modifier based on how the player has been doing.
+pub fn adaptive_difficulty_system(
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+    q_player: Query<&Health, With<Player>>,
+    time: Res<Time>,
+) {
+    if !adaptive.enabled {
+        return;
+    }
+    if q_player
+        .get_single()
+        .is_ok_and(|health| health.value < NEAR_DEATH_HEALTH)
+    {
+        adaptive.near_death = true;
+    }
+    if adaptive.window.tick(time.delta()).just_finished() {
+        adaptive.nudge();
+    }
+}
+
+// Anything that can die, and whether its death is worth points.
+type Mortal<'a> = (
+    Entity,
+    &'a Health,
+    &'a EntityType,
+    &'a Transform,
+    Has<BossAI>,
+    Has<Enemy>,
+);
+
 pub fn despawn_dead_system(
     mut commands: Commands,
-    entity_query: Query<(Entity, &Health, &EntityType)>,
+    entity_query: Query<Mortal>,
     asset_server: Res<AssetServer>,
+    mut impulse_writer: EventWriter<CameraImpulseEvent>,
+    mut detonation_writer: EventWriter<MissileDetonationEvent>,
+    mut score: ResMut<Score>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
 ) {
-    for (entity, health, et) in entity_query.iter() {
+    let mut rng = thread_rng();
+    for (entity, health, et, transform, is_boss, is_enemy) in entity_query.iter() {
         // If an entity's health has dropped to or below 0, despawn it.
         if health.value <= 0.0 {
+            // Missiles and mines that are shot down detonate early. The detonation handles the despawn.
+            if *et == EntityType::Missile {
+                detonation_writer.send(MissileDetonationEvent { entity });
+                continue;
+            }
+            // The boss going down should be felt.
+            if is_boss {
+                impulse_writer.send(CameraImpulseEvent {
+                    trauma: 1.0,
+                    hit_stop: 0.2,
+                });
+                score.points += 5000;
+            } else if is_enemy && *et == EntityType::Ship {
+                score.points += 100;
+            }
+            // Kills and drops belong to the adaptive mode, so normal games play out as the level script wrote them.
+            if adaptive.enabled && is_enemy && *et == EntityType::Ship {
+                adaptive.kills += 1;
+                if rng.gen::<f32>() < adaptive.drop_chance() {
+                    let kind = if rng.gen::<f32>() < 0.7 {
+                        PickupKind::Repair
+                    } else {
+                        PickupKind::Countermeasures
+                    };
+                    spawn_pickup(&mut commands, &asset_server, kind, transform.translation);
+                }
+            }
             if *et == EntityType::Ship || *et == EntityType::Missile {
                 commands.spawn((
                     AudioBundle {
@@ -331,6 +683,32 @@ pub fn despawn_dead_system(
     }
 }
 
+/// Spawns a pickup that floats in place for a while. Repairs glow green and countermeasures glow cyan.
+fn spawn_pickup(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    kind: PickupKind,
+    position: Vec3,
+) {
+    let color = match kind {
+        PickupKind::Repair => Color::GREEN,
+        PickupKind::Countermeasures => Color::CYAN,
+    };
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_xyz(position.x, position.y, -1.0)
+                .with_scale(GLOBAL_RESCALE_V * 3.0),
+            texture: asset_server.load("sprites/effects/star1.png"),
+            sprite: Sprite { color, ..default() },
+            ..default()
+        },
+        Pickup { kind },
+        SelfDestruct {
+            cd_timer: Timer::from_seconds(15.0, TimerMode::Once),
+        },
+    ));
+}
+
 pub fn handle_self_destruct_system(
     mut commands: Commands,
     mut entity_query: Query<(Entity, &mut SelfDestruct), With<SelfDestruct>>,
@@ -345,16 +723,17 @@ pub fn handle_self_destruct_system(
 pub fn setup_background_stars_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
 ) {
-    let win = window_query.get_single().unwrap();
     let mut rng = thread_rng();
-    for _ in 0..600 {
+    // Keep roughly the same density of stars as a 1600x1000 arena with 600 stars.
+    let count = (600.0 * arena.width * arena.height / (1600.0 * 1000.0)) as usize;
+    for _ in 0..count {
         commands.spawn((
             SpriteBundle {
                 transform: Transform::from_xyz(
-                    rng.gen::<f32>() * win.width(),
-                    rng.gen::<f32>() * win.height() * 20.0,
+                    rng.gen::<f32>() * arena.width,
+                    rng.gen::<f32>() * arena.height * 20.0,
                     -3.0,
                 )
                 .with_scale(GLOBAL_RESCALE_V),
@@ -372,7 +751,7 @@ pub fn spawn_missile_system(
     mut spawn_reader: EventReader<SpawnGuidedMissileEvent>,
 ) {
     for ev in spawn_reader.read() {
-        commands.spawn((
+        let mut missile = commands.spawn((
             SpriteBundle {
                 transform: ev.transform,
                 texture: asset_server.load("sprites/projectiles/guided_missile_red.png"),
@@ -402,9 +781,23 @@ pub fn spawn_missile_system(
             Missile {
                 turn_speed: 0.10,
                 fuel: 800.0,
+                seeker: ev.seeker,
+                explosion: Explosion {
+                    radius: 200.0,
+                    damage: 50.0,
+                    force: 1500.0,
+                    falloff: Falloff::Linear,
+                    blockable: true,
+                },
             },
             EntityType::Missile,
         ));
+        if let Some(target) = ev.target {
+            missile.insert(AiTarget(target));
+        }
+        if let Some(faction) = ev.faction {
+            missile.insert(faction);
+        }
     }
 }
 
@@ -414,10 +807,16 @@ pub fn spawn_mine_system(
     mut spawn_reader: EventReader<SpawnMineEvent>,
 ) {
     for ev in spawn_reader.read() {
-        commands.spawn((
+        // Mines are drawn a little smaller than the transform they were dropped with, and sized to match.
+        let size = ev.transform.scale.x / GLOBAL_RESCALE_V.x;
+        let mut mine = commands.spawn((
             SpriteBundle {
-                transform: ev.transform.with_scale(GLOBAL_RESCALE_V * 0.75),
+                transform: ev.transform.with_scale(ev.transform.scale * 0.75),
                 texture: asset_server.load("sprites/projectiles/mine.png"),
+                sprite: Sprite {
+                    color: Color::GRAY,
+                    ..default()
+                },
                 ..default()
             },
             Clipping {
@@ -425,8 +824,8 @@ pub fn spawn_mine_system(
             },
             CollisionBox {
                 shape: Shape::Circle,
-                width_radius: 20.0 * GLOBAL_RESCALE_C,
-                height: 20.0 * GLOBAL_RESCALE_C,
+                width_radius: 20.0 * GLOBAL_RESCALE_C * size,
+                height: 20.0 * GLOBAL_RESCALE_C * size,
             },
             Health { value: 10.0 },
             Mass { value: 100.0 },
@@ -434,17 +833,13 @@ pub fn spawn_mine_system(
                 dampening_factor: 0.995,
             },
             Velocity {
-                velocity: Vec3 {
-                    x: 0.0,
-                    y: 0.0,
-                    z: 0.0,
-                },
-            },
-            Missile {
-                turn_speed: 0.0,
-                fuel: 0.0,
+                velocity: ev.velocity,
             },
+            ev.mine.clone(),
             EntityType::Missile,
         ));
+        if let Some(faction) = ev.faction {
+            mine.insert(faction);
+        }
     }
 }
@@ -0,0 +1,129 @@
+//! This file contains steering behaviors for AI ships. Each behavior returns the direction a ship would like to
+//! travel in, scaled by how badly it wants to go that way. A ship adds up the behaviors it cares about, weighting
+//! each one, and passes the sum to `steer`, which turns and thrusts the ship as well as its turn speed and thruster
+//! allow. Ships can only thrust forward, so a ship facing the wrong way turns before it accelerates.
+
+use crate::components::*;
+use crate::geometry::*;
+use bevy::prelude::*;
+
+/// Head straight for a point.
+pub fn seek(from: Vec3, to: Vec3, arena: &Arena) -> Vec3 {
+    wrapped_direction(from, to, arena)
+}
+
+/// Head straight away from a point.
+pub fn flee(from: Vec3, threat: Vec3, arena: &Arena) -> Vec3 {
+    -wrapped_direction(from, threat, arena)
+}
+
+/// Head for a point, easing off as it gets within `slowing_radius` and stopping altogether at `stop_radius`.
+pub fn arrive(from: Vec3, to: Vec3, stop_radius: f32, slowing_radius: f32, arena: &Arena) -> Vec3 {
+    let distance = wrapped_distance(from, to, arena);
+    if distance <= stop_radius {
+        return Vec3::ZERO;
+    }
+    let urgency = ((distance - stop_radius) / slowing_radius).min(1.0);
+    wrapped_direction(from, to, arena) * urgency
+}
+
+/// Circle a point at `radius`, drifting inward or outward to correct the range.
+pub fn orbit(from: Vec3, center: Vec3, radius: f32, clockwise: bool, arena: &Arena) -> Vec3 {
+    let inward = wrapped_direction(from, center, arena);
+    let tangent = if clockwise {
+        Vec3::new(inward.y, -inward.x, 0.0)
+    } else {
+        Vec3::new(-inward.y, inward.x, 0.0)
+    };
+    let error = (wrapped_distance(from, center, arena) - radius) / radius;
+    (tangent + inward * error.clamp(-1.0, 1.0)).normalize_or_zero()
+}
+
+/// Push away from anything within `radius`, more strongly the closer it is. Obstacles in `ignore` don't count,
+/// so that a ship can leave out itself and whatever it is trying to hit.
+pub fn separation(
+    from: Vec3,
+    radius: f32,
+    ignore: &[Entity],
+    obstacles: &ObstacleGrid,
+    arena: &Arena,
+) -> Vec3 {
+    let mut push = Vec3::ZERO;
+    for obstacle in obstacles.nearby(from, radius) {
+        if ignore.contains(&obstacle.entity) {
+            continue;
+        }
+        let gap = wrapped_distance(from, obstacle.position, arena) - obstacle.radius;
+        if gap < radius {
+            push -=
+                wrapped_direction(from, obstacle.position, arena) * (1.0 - gap.max(0.0) / radius);
+        }
+    }
+    push
+}
+
+/// Swerve around the nearest obstacle lying across the ship's path within `look_ahead` pixels. `clearance` is how
+/// wide a berth the ship needs, usually its own collision radius.
+pub fn avoid_obstacles(
+    from: Vec3,
+    heading: Vec3,
+    look_ahead: f32,
+    clearance: f32,
+    ignore: &[Entity],
+    obstacles: &ObstacleGrid,
+    arena: &Arena,
+) -> Vec3 {
+    let heading = heading.truncate().normalize_or_zero();
+    if heading == Vec2::ZERO {
+        return Vec3::ZERO;
+    }
+    let mut nearest: Option<(f32, Vec2)> = None;
+    for obstacle in obstacles.nearby(from, look_ahead) {
+        if ignore.contains(&obstacle.entity) {
+            continue;
+        }
+        let offset = wrapped_delta(from, obstacle.position, arena).truncate();
+        let ahead = offset.dot(heading);
+        // How far the obstacle's center is from the line the ship is travelling along.
+        let lateral = offset - heading * ahead;
+        if ahead <= 0.0 || ahead > look_ahead || lateral.length() > obstacle.radius + clearance {
+            continue;
+        }
+        if nearest.map_or(true, |(distance, _)| ahead < distance) {
+            nearest = Some((ahead, lateral));
+        }
+    }
+    let Some((ahead, lateral)) = nearest else {
+        return Vec3::ZERO;
+    };
+    // Steer to whichever side the obstacle isn't on. Dead ahead, pick a side.
+    let away = if lateral == Vec2::ZERO {
+        heading.perp()
+    } else {
+        -lateral.normalize()
+    };
+    (away * (1.0 - ahead / look_ahead)).extend(0.0)
+}
+
+/// Turns the ship toward `desired` by at most `turn_speed`, and thrusts in proportion to how strongly it wants to
+/// move and how closely it is already facing that way. `acceleration` is the thruster's force divided by the ship's mass.
+pub fn steer(
+    transform: &mut Transform,
+    velocity: &mut Velocity,
+    desired: Vec3,
+    turn_speed: f32,
+    acceleration: f32,
+    delta_seconds: f32,
+) {
+    let desired = desired.truncate();
+    if desired.length() < 0.01 {
+        return;
+    }
+    let angle = transform.up().truncate().angle_between(desired);
+    transform.rotate_z(angle.clamp(-turn_speed, turn_speed));
+    let throttle = desired.length().min(1.0) * angle.cos().max(0.0);
+    velocity.velocity += transform.up() * acceleration * throttle * delta_seconds;
+    if velocity.velocity.length() > MAX_SPEED {
+        velocity.velocity = velocity.velocity.clamp_length_max(MAX_SPEED)
+    }
+}